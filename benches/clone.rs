@@ -0,0 +1,68 @@
+//! Measures how fast threads can clone (and drop) the same `Arc` while other threads use the same allocation in
+//! other ways. Compare the two layouts with:
+//!
+//! ```text
+//! cargo bench
+//! cargo bench --features cache_padded
+//! ```
+//!
+//! Padding only helps in the mixed workloads, where it keeps the strong count on a cache line of its own. When
+//! every thread clones, they all fight over the strong count either way.
+
+use arc::Arc;
+use std::hint::black_box;
+use std::sync::Barrier;
+use std::time::{Duration, Instant};
+
+const ITERATIONS: usize = 1_000_000;
+
+/// Runs `cloner` on half the threads and `other` on the rest, returning how long the cloners took per clone.
+fn run(threads: usize, cloner: impl Fn() + Sync, other: impl Fn() + Sync) -> Duration {
+    let cloners = threads.div_ceil(2);
+    let barrier = Barrier::new(threads);
+    let elapsed: Vec<Duration> = std::thread::scope(|s| {
+        let handles: Vec<_> = (0..threads)
+            .map(|i| {
+                let (barrier, cloner, other) = (&barrier, &cloner, &other);
+                s.spawn(move || {
+                    barrier.wait();
+                    let start = Instant::now();
+                    for _ in 0..ITERATIONS {
+                        if i < cloners {
+                            cloner();
+                        } else {
+                            other();
+                        }
+                    }
+                    start.elapsed()
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+    elapsed[..cloners].iter().sum::<Duration>() / (cloners * ITERATIONS) as u32
+}
+
+fn main() {
+    let threads = std::thread::available_parallelism().map_or(4, |n| n.get().max(2));
+    let padded = if cfg!(feature = "cache_padded") {
+        "padded"
+    } else {
+        "unpadded"
+    };
+    println!("{threads} threads, {padded} counts, time per clone and drop:");
+
+    let arc = Arc::new(42u64);
+    let clone = || drop(black_box(arc.clone()));
+
+    let time = run(threads, clone, || drop(black_box(Arc::downgrade(&arc))));
+    println!("  while others downgrade: {time:?}");
+
+    let time = run(threads, clone, || {
+        black_box(**black_box(&arc));
+    });
+    println!("  while others read:      {time:?}");
+
+    let time = run(threads, clone, clone);
+    println!("  while others clone:     {time:?}");
+}
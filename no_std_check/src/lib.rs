@@ -0,0 +1,35 @@
+//! Makes sure `arc` builds without `std`, by using it from a `#![no_std]` crate. To really check (without
+//! the rest of the workspace turning `std` back on), build this on its own for a target that has no `std`:
+//!
+//! ```text
+//! rustup target add thumbv7em-none-eabihf
+//! cargo build -p no_std_check --target thumbv7em-none-eabihf
+//! ```
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use arc::{Arc, Weak};
+
+pub fn shared_sum(numbers: Vec<u32>) -> u32 {
+    let numbers: Arc<[u32]> = Arc::from(numbers);
+    let clone = numbers.clone();
+    clone.iter().sum()
+}
+
+pub fn upgrade_after_drop(value: u32) -> Option<u32> {
+    let arc = Arc::new(value);
+    let weak: Weak<u32> = Arc::downgrade(&arc);
+    drop(arc);
+    weak.upgrade().map(|arc| *arc)
+}
+
+pub fn map_in_place(value: u32) -> Arc<i32> {
+    Arc::map(Arc::new(value), |value| value as i32 - 1)
+}
+
+pub fn try_new(value: u64) -> Result<Arc<u64>, arc::TryNewError<u64>> {
+    Arc::try_new(value)
+}
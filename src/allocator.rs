@@ -0,0 +1,34 @@
+// The allocator an `Arc<T, A>` gets its memory from (and gives it back to). With the `allocator_api` feature,
+// that's any `core::alloc::Allocator`. Without it, the stand-ins below take the place of the unstable trait and
+// `Global`. They can't be named outside the crate, so there `A` is always the (default) `Global`.
+
+#[cfg(feature = "allocator_api")]
+pub use alloc::alloc::Global;
+#[cfg(feature = "allocator_api")]
+pub use core::alloc::Allocator;
+
+#[cfg(not(feature = "allocator_api"))]
+pub use stable::*;
+
+#[cfg(not(feature = "allocator_api"))]
+mod stable {
+    use core::alloc::Layout;
+    use core::ptr::NonNull;
+
+    /// The global allocator, like the unstable `alloc::alloc::Global`.
+    #[derive(Clone, Copy, Default, Debug)]
+    pub struct Global;
+
+    /// The part of the unstable `core::alloc::Allocator` that code generic over `A` needs.
+    pub trait Allocator {
+        /// # Safety
+        /// `ptr` must have been allocated by this allocator, with `layout`.
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout);
+    }
+
+    impl Allocator for Global {
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            unsafe { alloc::alloc::dealloc(ptr.as_ptr(), layout) }
+        }
+    }
+}
@@ -1,16 +1,49 @@
+use crate::allocator::{Allocator, Global};
 use crate::arcdata::*;
+use crate::error::{AllocError, CloneError, TryNewError};
+use crate::projection::ArcProjection;
+use crate::weak::Weak;
 
-use std::ops::Deref;
-use std::ptr::NonNull;
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::ffi::CString;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::alloc::Layout;
+use core::any::Any;
+use core::borrow::Borrow;
+use core::cmp::Ordering as CmpOrdering;
+use core::error::Error;
+use core::ffi::CStr;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::iter::FusedIterator;
+#[cfg(feature = "nightly")]
+use core::marker::Unsize;
+use core::mem::MaybeUninit;
+use core::ops::Deref;
+#[cfg(feature = "nightly")]
+use core::ops::{CoerceUnsized, DispatchFromDyn};
+use core::panic::{RefUnwindSafe, UnwindSafe};
+use core::pin::Pin;
+#[cfg(feature = "std")]
+use std::ffi::{OsStr, OsString};
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
+#[cfg(feature = "std")]
+use std::thread;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
 
-pub struct Arc<T> {
-    data: NonNull<ArcData<T>>,
+pub struct Arc<T: ?Sized, A: Allocator = Global> {
+    data: NonNull<ArcData<T, A>>,
 }
 
-impl<T> Deref for Arc<T> {
+impl<T: ?Sized, A: Allocator> Deref for Arc<T, A> {
     type Target = T;
     fn deref(&self) -> &Self::Target {
-        &self.data().data
+        // SAFETY: Since there's an `Arc<T>` (this one), the data has not been dropped yet.
+        unsafe { &*self.data().data.get() }
     }
 }
 
@@ -30,19 +63,584 @@ impl<T> Arc<T> {
         }
     }
 
+    /// Creates a new `Arc<MaybeUninit<T>>` whose data is left uninitialized, so it can be written in place
+    /// (e.g. through [`Arc::get_mut`]) before calling [`Arc::assume_init`].
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let mut arc = Arc::<u64>::new_uninit();
+    /// Arc::get_mut(&mut arc).unwrap().write(42);
+    /// let arc = unsafe { arc.assume_init() };
+    /// assert_eq!(*arc, 42);
+    /// ```
+    pub fn new_uninit() -> Arc<MaybeUninit<T>> {
+        Self::try_new_uninit().unwrap_or_else(|AllocError| {
+            alloc::alloc::handle_alloc_error(Layout::new::<ArcData<MaybeUninit<T>>>())
+        })
+    }
+
+    /// Creates a new `Arc<MaybeUninit<T>>` whose data is filled with zero bytes. Calling [`Arc::assume_init`] on
+    /// it is only sound if all zeroes are a valid `T` (as they are for integers, floats, arrays of them, ...).
+    ///
+    /// This is cheaper than writing the zeroes by hand, since the allocator can often provide memory that is already
+    /// zeroed (e.g. fresh pages from the OS).
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let arc = unsafe { Arc::<[u8; 4096]>::new_zeroed().assume_init() };
+    /// assert!(arc.iter().all(|&b| b == 0));
+    /// ```
+    pub fn new_zeroed() -> Arc<MaybeUninit<T>> {
+        Self::try_new_zeroed().unwrap_or_else(|AllocError| {
+            alloc::alloc::handle_alloc_error(Layout::new::<ArcData<MaybeUninit<T>>>())
+        })
+    }
+
+    /// Creates a new `Arc<T>` that calls `finalizer` on the data right before it is dropped, i.e. when the last
+    /// `Arc<T>` pointing to it goes away.
+    ///
+    /// The finalizer runs exactly once, on whichever thread drops the last `Arc<T>`. If it panics, the data is
+    /// still dropped and the allocation freed (once the [`Weak`]s are gone) while unwinding. It moves along with
+    /// the data in [`Arc::make_mut`], but functions that move the data out ([`Arc::try_unwrap`],
+    /// [`Arc::into_inner`], ...) drop it without calling it.
+    ///
+    /// # Arguments
+    /// * `data` - The data to be stored in the `Arc<T>`.
+    /// * `finalizer` - A closure called with the data right before it's dropped.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    ///
+    /// static UNREGISTERED: AtomicBool = AtomicBool::new(false);
+    ///
+    /// let handle = Arc::new_with_finalizer(42, |fd: &mut i32| {
+    ///     assert_eq!(*fd, 42);
+    ///     UNREGISTERED.store(true, Ordering::Relaxed);
+    /// });
+    /// let clone = handle.clone();
+    /// drop(handle);
+    /// assert!(!UNREGISTERED.load(Ordering::Relaxed));
+    /// drop(clone);
+    /// assert!(UNREGISTERED.load(Ordering::Relaxed));
+    /// ```
+    pub fn new_with_finalizer<F>(data: T, finalizer: F) -> Self
+    where
+        T: 'static,
+        F: FnOnce(&mut T) + Send + 'static,
+    {
+        let this = Self::new(data);
+        // The finalizer is only ever called by `ArcData::drop_data`, with the address of this very `T` (which
+        // stays the same even if `this` is later turned into, say, an `Arc<dyn Any>`).
+        let finalizer: Finalizer =
+            Box::new(move |data: *mut u8| finalizer(unsafe { &mut *data.cast::<T>() }));
+        // SAFETY: Nothing else knows about `this` yet.
+        unsafe { *this.data().finalizer.get() = Some(Box::new(finalizer)) };
+        this
+    }
+
+    /// Creates a new `Arc<T>` whose data is initialized in place by `init`, directly in its final allocation.
+    ///
+    /// Unlike [`Arc::new`], the data never has to exist anywhere else first, so even values too large for the
+    /// stack can be built this way (by writing their fields through [`MaybeUninit::as_mut_ptr`]). `init` must
+    /// return a reference to the data it initialized, i.e. the result of [`MaybeUninit::write`] or
+    /// [`MaybeUninit::assume_init_mut`] on the slot it was given.
+    ///
+    /// If `init` panics, the allocation is freed without dropping anything.
+    ///
+    /// # Arguments
+    /// * `init` - A closure initializing the data in place.
+    ///
+    /// # Panics
+    /// Panics if `init` returns a reference to anything but the slot it was given.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// use std::mem::MaybeUninit;
+    ///
+    /// struct State {
+    ///     id: u32,
+    ///     table: [u64; 1 << 20], // 8 MiB
+    /// }
+    ///
+    /// let state = Arc::new_with(|slot: &mut MaybeUninit<State>| {
+    ///     let ptr = slot.as_mut_ptr();
+    ///     unsafe {
+    ///         (&raw mut (*ptr).id).write(42);
+    ///         for i in 0..1 << 20 {
+    ///             (&raw mut (*ptr).table[i]).write(i as u64);
+    ///         }
+    ///         slot.assume_init_mut()
+    ///     }
+    /// });
+    /// assert_eq!((state.id, state.table[1000]), (42, 1000));
+    /// ```
+    pub fn new_with<F>(init: F) -> Self
+    where
+        F: FnOnce(&mut MaybeUninit<T>) -> &mut T,
+    {
+        // If `init` panics, dropping `uninit` frees the allocation, and a `MaybeUninit<T>` has nothing to drop.
+        let mut uninit = Self::new_uninit();
+        // SAFETY: Nothing else knows about `uninit` yet.
+        let slot = unsafe { Arc::get_mut_unchecked(&mut uninit) };
+        let slot_ptr = slot.as_mut_ptr();
+        // A `&mut T` pointing to the slot can only exist if the slot holds a valid `T`.
+        let initialized: *mut T = init(slot);
+        assert!(
+            core::ptr::eq(initialized, slot_ptr),
+            "`new_with` closure must return a reference to the slot it was given"
+        );
+        unsafe { uninit.assume_init() }
+    }
+
+    /// Creates a new `Arc<T>` containing data of type `T`, or returns a [`TryNewError`] holding on to `data` if
+    /// the memory for it can't be allocated, instead of aborting like [`Arc::new`].
+    ///
+    /// # Arguments
+    /// * `data` - The data to be stored in the `Arc<T>`.
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let arc = Arc::try_new(42).unwrap();
+    /// assert_eq!(*arc, 42);
+    ///
+    /// // If allocating fails, the data isn't lost.
+    /// let data = match Arc::try_new(String::from("hello")) {
+    ///     Ok(arc) => Arc::unwrap_or_clone(arc),
+    ///     Err(error) => error.into_inner(),
+    /// };
+    /// assert_eq!(data, "hello");
+    /// ```
+    pub fn try_new(data: T) -> Result<Self, TryNewError<T>> {
+        ArcData::try_allocate(data)
+            .map(Self::from_inner)
+            .map_err(TryNewError)
+    }
+
+    /// Like [`Arc::new_uninit`], but returns an [`AllocError`] if the memory can't be allocated, instead of aborting.
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let data = String::from("hello");
+    /// // `data` is only moved once there is somewhere to put it, so it's never lost to an allocation failure.
+    /// let mut arc = Arc::<String>::try_new_uninit().unwrap();
+    /// Arc::get_mut(&mut arc).unwrap().write(data);
+    /// let arc = unsafe { arc.assume_init() };
+    /// assert_eq!(*arc, "hello");
+    /// ```
+    pub fn try_new_uninit() -> Result<Arc<MaybeUninit<T>>, AllocError> {
+        ArcData::try_allocate_uninit(false).map(Arc::from_inner)
+    }
+
+    /// Like [`Arc::new_zeroed`], but returns an [`AllocError`] if the memory can't be allocated, instead of aborting.
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let arc = unsafe { Arc::<[u64; 512]>::try_new_zeroed().unwrap().assume_init() };
+    /// assert!(arc.iter().all(|&x| x == 0));
+    /// ```
+    pub fn try_new_zeroed() -> Result<Arc<MaybeUninit<T>>, AllocError> {
+        ArcData::try_allocate_uninit(true).map(Arc::from_inner)
+    }
+
+    /// Creates a new `Pin<Arc<T>>`, or returns a [`TryNewError`] holding on to `data` (which was never pinned)
+    /// if the memory for it can't be allocated. See [`Arc::pin`] and [`Arc::try_new`].
+    /// # Arguments
+    /// * `data` - The data to be stored (and pinned) in the `Arc<T>`.
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let pinned = Arc::try_pin(42).unwrap();
+    /// assert_eq!(*pinned, 42);
+    /// ```
+    pub fn try_pin(data: T) -> Result<Pin<Self>, TryNewError<T>> {
+        // SAFETY: Same as in `pin`.
+        Self::try_new(data).map(|arc| unsafe { Pin::new_unchecked(arc) })
+    }
+
+    /// Creates a new `Pin<Arc<T>>`, guaranteeing the data won't move again until it is dropped, even if `T` is
+    /// not [`Unpin`].
+    ///
+    /// This holds because no `Arc<T>` function ever moves the data out of (or within) its allocation, except
+    /// the ones that consume the `Arc<T>` itself ([`Arc::try_unwrap`], [`Arc::into_inner`], ...), and those
+    /// can't be reached through a `Pin<Arc<T>>` unless `T: Unpin` (see [`Pin::into_inner`]). The same goes for
+    /// [`Arc::get_mut`] and [`Arc::make_mut`], which need a `&mut Arc<T>`.
+    ///
+    /// # Arguments
+    /// * `data` - The data to be stored (and pinned) in the `Arc<T>`.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// use std::marker::PhantomPinned;
+    /// use std::pin::Pin;
+    ///
+    /// struct Pinned {
+    ///     value: i32,
+    ///     _pin: PhantomPinned,
+    /// }
+    ///
+    /// impl Pinned {
+    ///     fn value(self: Pin<&Self>) -> i32 {
+    ///         self.value
+    ///     }
+    /// }
+    ///
+    /// let pinned = Arc::pin(Pinned { value: 42, _pin: PhantomPinned });
+    /// let clone = pinned.clone();
+    /// assert_eq!(clone.as_ref().value(), 42);
+    ///
+    /// // Pinning something `Unpin` works too, and derefs like any other pointer.
+    /// let number: Pin<Arc<i32>> = Arc::pin(7);
+    /// assert_eq!(*number, 7);
+    /// assert_eq!(*number.as_ref().get_ref() + 1, 8);
+    /// ```
+    pub fn pin(data: T) -> Pin<Self> {
+        // SAFETY: See above, the data is never moved while any `Arc<T>` or `Weak<T>` pointing to it exists.
+        unsafe { Pin::new_unchecked(Self::new(data)) }
+    }
+
+    /// Creates a new `Arc<T>` containing data that holds a [`Weak`] pointer to itself.
+    ///
+    /// `data_fn` is given a [`Weak`] pointing to the allocation that will hold its return value. Since that
+    /// value doesn't exist yet, upgrading the [`Weak`] inside `data_fn` returns [`Option::None`], but it can be
+    /// cloned and stored, and will upgrade normally once `new_cyclic` returns.
+    ///
+    /// If `data_fn` panics, the allocation is freed and nothing is dropped.
+    ///
+    /// # Arguments
+    /// * `data_fn` - A closure building the data from a [`Weak`] pointer to it.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::{Arc, Weak};
+    ///
+    /// struct Node {
+    ///     me: Weak<Node>,
+    ///     value: i32,
+    /// }
+    ///
+    /// let node = Arc::new_cyclic(|me| Node {
+    ///     me: me.clone(),
+    ///     value: 42,
+    /// });
+    /// assert_eq!(node.me.upgrade().unwrap().value, 42);
+    /// ```
+    pub fn new_cyclic<F: FnOnce(&Weak<T>) -> T>(data_fn: F) -> Self {
+        // Start with a strong count of 0, so the `Weak<T>` can't be upgraded while the data is missing, and a
+        // weak count of 1 for the `Weak<T>` handed to `data_fn`.
+        let uninit = Box::new(ArcData {
+            refs: Counter::new(0),
+            weak: Counter::new(1),
+            finalizer: UnsafeCell::new(None),
+            #[cfg(feature = "std")]
+            waiter: AtomicPtr::new(core::ptr::null_mut()),
+            alloc: ManuallyDrop::new(Global),
+            data: UnsafeCell::new(ManuallyDrop::new(MaybeUninit::<T>::uninit())),
+        });
+        // `ArcData` is `repr(C)`, so this is the same layout as the `ArcData<T>` we pretend it is.
+        let data = NonNull::from(Box::leak(uninit)).cast::<ArcData<T>>();
+
+        // If `data_fn` panics, dropping this `Weak<T>` frees the allocation. Its data is in a `ManuallyDrop`,
+        // so the uninitialized `T` is never dropped.
+        let weak = Weak { data };
+        let value = data_fn(&weak);
+
+        unsafe {
+            // The data lives in an `UnsafeCell`, so writing it is fine even if clones of `weak` are already
+            // looking at the counters from other threads.
+            data.as_ref().data.get().write(ManuallyDrop::new(value));
+            // The release store "happens before" the acquire compare-exchange of any `Weak::upgrade` that sees
+            // the 1, so upgraded `Arc<T>`s see the data we just wrote.
+            data.as_ref().refs.store(1, Ordering::Release);
+        }
+        // `weak` becomes the implicit weak reference held collectively by all `Arc<T>`s.
+        core::mem::forget(weak);
+        Self::from_inner(data)
+    }
+}
+
+#[cfg(feature = "allocator_api")]
+impl<T, A: Allocator> Arc<T, A> {
+    /// Like [`Arc::new`], but gets the memory from `alloc` instead of the global allocator. The `Arc<T, A>` and
+    /// its clones keep `alloc` around, and give the memory back to it once the last `Arc` and `Weak` are gone.
+    ///
+    /// # Arguments
+    /// * `data` - The data to be stored in the `Arc<T, A>`.
+    /// * `alloc` - The allocator to get the memory from.
+    /// # Examples
+    /// ```
+    /// #![feature(allocator_api)]
+    /// use arc::Arc;
+    /// use std::alloc::System;
+    /// let arc = Arc::new_in(42, System);
+    /// assert_eq!(*arc, 42);
+    /// ```
+    pub fn new_in(data: T, alloc: A) -> Self {
+        match ArcData::try_allocate_in(data, alloc) {
+            Ok(ptr) => Self::from_inner(ptr),
+            Err(_) => alloc::alloc::handle_alloc_error(Layout::new::<ArcData<T, A>>()),
+        }
+    }
+
+    /// Like [`Arc::new_in`], but returns a [`TryNewError`] holding on to `data` (and drops `alloc`) if `alloc`
+    /// can't provide the memory, instead of aborting. See [`Arc::try_new`].
+    /// # Examples
+    /// ```
+    /// #![feature(allocator_api)]
+    /// use arc::Arc;
+    /// use std::alloc::System;
+    /// let arc = Arc::try_new_in(42, System).unwrap();
+    /// assert_eq!(*arc, 42);
+    /// ```
+    pub fn try_new_in(data: T, alloc: A) -> Result<Self, TryNewError<T>> {
+        ArcData::try_allocate_in(data, alloc)
+            .map(Self::from_inner)
+            .map_err(|(data, _)| TryNewError(data))
+    }
+}
+
+impl<T: ?Sized, A: Allocator> Arc<T, A> {
+    /// Clones this `Arc<T>` like [`Clone::clone`], but returns an error instead of aborting the process if there
+    /// are already too many `Arc<T>`s (a number no real program gets anywhere near by accident, but one that
+    /// could be reached by leaking clones on purpose).
+    ///
+    /// # Arguments
+    /// * `this` - A reference to an `Arc<T>`.
+    ///
+    /// # Errors
+    /// Returns [`CloneError`] if the strong count is at its limit (`usize::MAX / 3`).
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let arc = Arc::new(42);
+    /// let clone = Arc::try_clone(&arc).unwrap();
+    /// assert!(Arc::ptr_eq(&arc, &clone));
+    /// ```
+    pub fn try_clone(this: &Self) -> Result<Self, CloneError> {
+        // Unlike `clone`, this must never take the count past the limit (not even for a moment), so it checks
+        // before incrementing.
+        let mut n = this.data().refs.load(Ordering::Relaxed);
+        loop {
+            // `WAITING` doesn't count (see `into_inner_blocking`).
+            if n & !WAITING >= MAX_REFS {
+                return Err(CloneError);
+            }
+            // Relaxed, like in `clone`: the new `Arc<T>` doesn't need to observe anything the others did.
+            match this.data().refs.compare_exchange_weak(
+                n,
+                n + 1,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return Ok(Self { data: this.data }),
+                Err(e) => n = e,
+            }
+        }
+    }
+
+    /// Returns an iterator yielding `n` clones of this `Arc<T>`, with a single atomic increment of the strong count
+    /// (instead of one per clone) when it's created.
+    ///
+    /// The clones are handed out lazily, and any the iterator still holds when it's dropped are given back, again
+    /// with a single atomic decrement.
+    ///
+    /// # Arguments
+    /// * `this` - A reference to an `Arc<T>`.
+    /// * `n` - The number of clones.
+    ///
+    /// # Panics
+    /// Panics if `n` is larger than `usize::MAX / 6`, which couldn't ever be handed out anyway.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let job = Arc::new(42);
+    /// let workers: Vec<Arc<i32>> = Arc::clones(&job, 64).collect();
+    /// assert_eq!(Arc::strong_count(&job), 65);
+    /// ```
+    pub fn clones(this: &Self, n: usize) -> Clones<T, A> {
+        // Small enough that adding it to a count that passed the check below still stays clear of `WAITING`.
+        assert!(n <= usize::MAX / 6, "too many clones requested");
+        // Same overflow check as in `clone`: as long as there aren't a huge number of threads doing this at the
+        // same time, the count can't get anywhere near wrapping around before we abort.
+        if this.data().refs.fetch_add(n, Ordering::Relaxed) & !WAITING > MAX_REFS {
+            abort();
+        }
+        Clones {
+            data: this.data,
+            remaining: n,
+        }
+    }
+
+    /// Returns a reference to the allocator this `Arc<T, A>` got its memory from.
+    /// # Examples
+    /// ```
+    /// #![feature(allocator_api)]
+    /// use arc::Arc;
+    /// use std::alloc::System;
+    /// let arc = Arc::new_in(42, System);
+    /// let _: &System = Arc::allocator(&arc);
+    /// ```
+    #[cfg(feature = "allocator_api")]
+    pub fn allocator(this: &Self) -> &A {
+        &this.data().alloc
+    }
+
+    /// Creates a new [`Weak`] pointer to the data of this `Arc<T>`.
+    ///
+    /// The [`Weak`] does not keep the data alive: once every `Arc<T>` is dropped, the data is dropped too.
+    /// Only the allocation (holding the reference counts) stays around until the last [`Weak`] is gone.
+    ///
+    /// # Arguments
+    /// * `this` - A reference to an `Arc<T>`.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let arc = Arc::new(42);
+    /// let weak = Arc::downgrade(&arc);
+    /// ```
+    pub fn downgrade(this: &Self) -> Weak<T, A> {
+        let mut n = this.data().weak.load(Ordering::Relaxed);
+        loop {
+            if n == usize::MAX {
+                // The weak count is "locked" by `is_unique` (see there), so wait for it to be unlocked.
+                core::hint::spin_loop();
+                n = this.data().weak.load(Ordering::Relaxed);
+                continue;
+            }
+            if n > MAX_REFS {
+                abort();
+            }
+            // Acquire on success synchronizes with the release store unlocking the weak count in `is_unique`,
+            // so a `get_mut` that just finished is done with the data before the new `Weak<T>` can upgrade.
+            if let Err(e) = this.data().weak.compare_exchange_weak(
+                n,
+                n + 1,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                n = e;
+                continue;
+            }
+            return Weak { data: this.data };
+        }
+    }
+
     /// Returns the number of references to this `Arc<T>`.
     /// # Examples
     /// ```
+    /// # #![allow(deprecated)]
     /// use arc::Arc;
     /// let arc = Arc::new(42);
     /// assert_eq!(arc.ref_count(), 1);
     /// ```
+    #[deprecated(note = "use `Arc::strong_count` instead")]
     pub fn ref_count(&self) -> usize {
-        self.data().refs.load(Ordering::Relaxed)
+        Self::strong_count(self)
+    }
+
+    /// Returns the number of `Arc<T>`s pointing to this allocation.
+    ///
+    /// The count is loaded with [`Ordering::Acquire`], so it is at least coherent with drops that already
+    /// happened on other threads, but other threads may still clone or drop their `Arc<T>`s right after.
+    /// Treat the number as approximate unless nothing else can access this allocation concurrently.
+    ///
+    /// # Arguments
+    /// * `this` - A reference to an `Arc<T>`.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let arc = Arc::new(42);
+    /// let clone = arc.clone();
+    /// assert_eq!(Arc::strong_count(&arc), 2);
+    /// ```
+    pub fn strong_count(this: &Self) -> usize {
+        this.data().refs.load(Ordering::Acquire) & !WAITING
+    }
+
+    /// Returns the number of [`Weak`]s pointing to this allocation.
+    ///
+    /// Like [`Arc::strong_count`], this is only a snapshot: other threads may create or drop `Weak<T>`s at any time.
+    ///
+    /// # Arguments
+    /// * `this` - A reference to an `Arc<T>`.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let arc = Arc::new(42);
+    /// assert_eq!(Arc::weak_count(&arc), 0);
+    /// let weak = Arc::downgrade(&arc);
+    /// assert_eq!(Arc::weak_count(&arc), 1);
+    /// ```
+    pub fn weak_count(this: &Self) -> usize {
+        let n = this.data().weak.load(Ordering::Acquire);
+        if n == usize::MAX {
+            // Locked by `is_unique`, which only succeeds if there are no `Weak<T>`s.
+            0
+        } else {
+            // Since `this` is an `Arc<T>`, the weak count includes the implicit weak reference held collectively by
+            // all `Arc<T>`s, which we don't want to report.
+            n - 1
+        }
+    }
+
+    /// Returns `true` if this is the only `Arc<T>` pointing to the data and there are no [`Weak`]s (which could be
+    /// upgraded into more `Arc<T>`s).
+    ///
+    /// Unlike comparing [`Arc::strong_count`] to 1, this takes the [`Weak`]s into account and synchronizes with
+    /// the drops of all other `Arc<T>`s and [`Weak`]s, so when it returns `true`, they are done with the data.
+    /// With only a `&Arc<T>`, the answer may be stale as soon as it's returned, though: another thread holding the
+    /// same `Arc<T>` by reference could clone or downgrade it right after. It can only be relied upon while
+    /// holding a `&mut Arc<T>` (as [`Arc::get_mut`] does).
+    ///
+    /// # Arguments
+    /// * `this` - A reference to an `Arc<T>`.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let arc = Arc::new(42);
+    /// assert!(Arc::is_unique(&arc));
+    /// let weak = Arc::downgrade(&arc);
+    /// assert!(!Arc::is_unique(&arc));
+    /// drop(weak);
+    /// assert!(Arc::is_unique(&arc));
+    /// ```
+    pub fn is_unique(this: &Self) -> bool {
+        // Checking the two counts one after the other isn't enough on its own: between reading a weak count of 1
+        // and a strong count of 1, another `Arc<T>` could `downgrade` itself and then be dropped, leaving a `Weak<T>`
+        // behind that could upgrade while the caller hands out a mutable reference.
+        // So we first "lock" the weak count by swapping its 1 (just the implicit weak reference) for `usize::MAX`,
+        // which makes `downgrade` wait. `Weak::clone` doesn't need to check for the lock, since a weak count of 1
+        // means there is no `Weak<T>` to clone.
+        // Acquire synchronizes with the release decrement in `Weak<T>`'s `Drop`, so any upgraded `Arc<T>` that
+        // was dropped before its `Weak<T>` is done with the data.
+        if this
+            .data()
+            .weak
+            .compare_exchange(1, usize::MAX, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            return false;
+        }
+        // The acquire load synchronizes with the release decrement in `drop`, so the other (now dropped) `Arc<T>`s
+        // are done with the data before the caller hands out a mutable reference to it.
+        let unique = this.data().refs.load(Ordering::Acquire) == 1;
+        // Unlock the weak count. Release makes sure our check "happens before" any `downgrade` that sees the 1.
+        this.data().weak.store(1, Ordering::Release);
+        unique
     }
 
     /// Returns an [`Option::Some`] containing a mutable reference to the data if this is the only reference.
-    /// Otherwise, returns [`Option::None`].
+    /// Otherwise (including when there are [`Weak`]s, which could be upgraded), returns [`Option::None`].
     ///
     /// # Arguments
     /// * `this` - A mutable reference to an `Arc<T>`.
@@ -59,58 +657,2023 @@ impl<T> Arc<T> {
     pub fn get_mut(this: &mut Self) -> Option<&mut T> {
         // This method takes a named mutable reference to something of type `Self` to reduce
         // ambiguity (it becomes clear the user is calling `get_mut` on the Arc<T> and not on the Deref `T`).
-        if this.data().refs.load(Ordering::Relaxed) == 1 {
-            // The relaxed load is a part of the all-thread-spanning total-modification-order.
-            // If we relaxed-load a 1, we know our ref count is a 1 at that instant.
-            fence(Ordering::Acquire);
-            // The above acquire fence ensures that nothing after it gets reordered before it. This
-            // ensures that this function doesn't return a mutable reference to the data before we
-            // know that we are the ONLY arc (i.e., that the ref count is 1).
-            // An acquire-fence was chosen here over an Acquire load for efficiency: the fence will ONLY run if the ref count is 1.
-
-            // There is additionally no possibility that the ref count atomic integer gets incremented at *any time* after the relaxed load of a 1 in this function, since:
-            // a. We must be the only Arc with this shared ArcData (ref count == 1)
-            // b. The compiler will not let any other functions which borrow (mutably or immutably) this Arc (and change the ref count) be called since we have a mutable (exclusive) reference.
-            Some(&mut this.data_mut().data)
+        if Self::is_unique(this) {
+            // There is no possibility that either count gets incremented at *any time* after `is_unique` returns
+            // true, since:
+            // a. We must be the only Arc with this shared ArcData (ref count == 1), and there are no Weaks that
+            //    could be upgraded (weak count == 1, the implicit weak reference).
+            // b. The compiler will not let any other functions which borrow (mutably or immutably) this Arc (and
+            //    change the counts, e.g. `clone` or `downgrade`) be called since we have a mutable (exclusive) reference.
+            Some(&mut **this.data_mut().data.get_mut())
         } else {
             None
         }
     }
 
-    // Private functions
-    fn data(&self) -> &ArcData<T> {
-        unsafe { self.data.as_ref() }
+    /// Returns a raw pointer to the data of this `Arc<T>`, without changing any reference counts.
+    ///
+    /// The pointer is the same for every clone of this `Arc<T>`, and stays valid for reads for as long as at
+    /// least one `Arc<T>` pointing to this allocation exists. It points to the data itself, not to the
+    /// reference counts in front of it, so it must *not* be used to recreate an `Arc<T>` directly.
+    ///
+    /// # Arguments
+    /// * `this` - A reference to an `Arc<T>`.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let arc = Arc::new(42);
+    /// let ptr = Arc::as_ptr(&arc);
+    /// assert_eq!(ptr, Arc::as_ptr(&arc.clone()));
+    /// assert_eq!(unsafe { *ptr }, 42);
+    /// ```
+    pub fn as_ptr(this: &Self) -> *const T {
+        // Not going through `Deref` keeps the provenance of the whole allocation, so the pointer can later be
+        // stepped back to the start of the `ArcData<T>` in `from_raw`.
+        ArcData::data_ptr(this.data)
     }
 
-    fn data_mut(&mut self) -> &mut ArcData<T> {
-        unsafe { self.data.as_mut() }
+    /// Consumes this `Arc<T>` without ever releasing its strong reference, and returns a reference to the data
+    /// that is valid for as long as the caller likes (e.g. `'static`).
+    ///
+    /// The strong count stays at least 1 forever, so the data is never dropped and the allocation is never freed.
+    /// Other `Arc<T>`s and [`Weak`]s pointing to it keep working normally. Turning the reference back into an
+    /// `Arc<T>` with [`Arc::from_raw`] (or releasing it with [`Arc::decrement_strong_count`]) is only sound if
+    /// the leaked reference, and every reference derived from it, is never used again afterwards.
+    ///
+    /// # Arguments
+    /// * `this` - The `Arc<T>` to leak.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// use std::sync::OnceLock;
+    /// static LOG_LEVEL: OnceLock<&'static str> = OnceLock::new();
+    /// let level = LOG_LEVEL.get_or_init(|| Arc::leak(Arc::<str>::from("verbose")));
+    /// assert_eq!(*level, "verbose");
+    /// ```
+    pub fn leak<'a>(this: Self) -> &'a T
+    where
+        T: 'a,
+    {
+        // Forgetting `this` keeps its strong reference (and the data) alive for good.
+        let this = ManuallyDrop::new(this);
+        unsafe { &*this.data().data.get() }
     }
-}
 
-impl<T> Clone for Arc<T> {
-    fn clone(&self) -> Self {
-        if self.data().refs.fetch_add(1, Ordering::Relaxed) > usize::MAX / 3 {
-            std::process::abort();
-        }
-        Self { data: self.data }
+    /// Returns `true` if the two `Arc<T>`s point to the same allocation, regardless of whether their data is equal.
+    ///
+    /// Only the addresses are compared, ignoring any metadata (like a slice length or a vtable) a pointer to
+    /// the allocation might carry. Two `Arc<T>`s created separately are never `ptr_eq`, even if their data is
+    /// equal (or zero-sized: every allocation also holds the reference counts, so it's never empty).
+    ///
+    /// # Arguments
+    /// * `this` - A reference to an `Arc<T>`.
+    /// * `other` - A reference to another `Arc<T>`.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let arc = Arc::new(42);
+    /// assert!(Arc::ptr_eq(&arc, &arc.clone()));
+    /// assert!(!Arc::ptr_eq(&arc, &Arc::new(42)));
+    /// ```
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        core::ptr::addr_eq(this.data.as_ptr(), other.data.as_ptr())
     }
-}
 
-impl<T> Drop for Arc<T> {
-    fn drop(&mut self) {
-        // The atomic memory orderings here are only to prevent the compiler from reordering (and maybe some wacky architectures) the drop (not an atomic operation)
-        // before the fetch_sub.
-        //
-        // x86-64, ARM, PowerPC and other architectures that use MESI or MOESI cache coherence protocols already guarantee that even a
-        // relaxed atomic operation will be "immediately" visible to all other cores in the system
-        // (since it had to get the cache line in exclusive mode to perform the operation).
-        if self.data().refs.fetch_sub(1, Ordering::Release) == 1 {
-            // The above release and everything before it "happens before" the following acquire fence and everything after it.
-            fence(Ordering::Acquire);
-            drop(unsafe { Box::from_raw(self.data.as_ptr()) });
-        }
-    }
-}
+    /// Returns a mutable reference to the data, without checking whether this is the only reference to it.
+    ///
+    /// This skips the atomic operations [`Arc::get_mut`] needs, which is useful when uniqueness is already known by
+    /// construction (for example, right after creating the `Arc<T>`, before it was ever cloned or downgraded).
+    ///
+    /// # Arguments
+    /// * `this` - A mutable reference to an `Arc<T>`.
+    ///
+    /// # Safety
+    /// For as long as the returned reference is alive, no other `Arc<T>` or [`Weak`] pointing to the same
+    /// allocation may be dereferenced (or upgraded and then dereferenced). In particular, the data must not be
+    /// accessed from any other thread in the meantime.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let mut arc = Arc::new([0u8; 1024]);
+    /// // Nothing else knows about `arc` yet.
+    /// unsafe { Arc::get_mut_unchecked(&mut arc) }.fill(42);
+    /// let shared = arc.clone();
+    /// assert!(shared.iter().all(|&b| b == 42));
+    /// ```
+    ///
+    /// Initializing the data of a fresh [`Arc::new_uninit`], which can't have been shared yet either:
+    /// ```
+    /// use arc::Arc;
+    /// let mut arc = Arc::<[u64; 4]>::new_uninit();
+    /// // SAFETY: `arc` was just created, so there are no other `Arc`s or `Weak`s.
+    /// let slot = unsafe { Arc::get_mut_unchecked(&mut arc) };
+    /// for (i, x) in slot.write([0; 4]).iter_mut().enumerate() {
+    ///     *x = 1 << i;
+    /// }
+    /// // SAFETY: Every element was written above.
+    /// let arc = unsafe { arc.assume_init() };
+    /// assert_eq!(*arc, [1, 2, 4, 8]);
+    /// ```
+    pub unsafe fn get_mut_unchecked(this: &mut Self) -> &mut T {
+        // The caller promises nothing else is looking at the data, and our `&mut Self` keeps this `Arc<T>` (and
+        // thus the data) alive for the duration of the borrow.
+        unsafe { &mut *this.data().data.get() }
+    }
+
+    /// Drops this `Arc<T>` without recursing into the `Arc<T>`s the data holds, so that dropping a long chain
+    /// of them (like a linked list of `Arc<Node>`s) can't overflow the stack.
+    ///
+    /// If this is the last `Arc<T>`, `unlink` gets the data right before it's dropped, and returns the `Arc<T>`s
+    /// it should let go of (taking them out of the data, e.g. with [`Option::take`], so that the data's own
+    /// destructor doesn't drop them). Those are then dropped the same way, one after the other, instead of from
+    /// within the destructor. `Arc<T>`s that `unlink` leaves in the data are dropped recursively, as usual.
+    ///
+    /// # Arguments
+    /// * `this` - The `Arc<T>` to drop.
+    /// * `unlink` - Takes the `Arc<T>`s out of data that's about to be dropped.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// struct Node {
+    ///     next: Option<Arc<Node>>,
+    /// }
+    ///
+    /// let mut list = Arc::new(Node { next: None });
+    /// for _ in 0..100_000 {
+    ///     list = Arc::new(Node { next: Some(list) });
+    /// }
+    /// Arc::drop_iterative(list, |node| node.next.take());
+    /// ```
+    pub fn drop_iterative<I>(this: Self, mut unlink: impl FnMut(&mut T) -> I)
+    where
+        I: IntoIterator<Item = Self>,
+    {
+        // The `Arc<T>`s left to drop. If `unlink` panics, the ones still here are dropped (recursively) while
+        // unwinding.
+        let mut pending = Vec::from([this]);
+        while let Some(arc) = pending.pop() {
+            let mut more = None;
+            ManuallyDrop::new(arc).release(|data| more = Some(unlink(data)));
+            pending.extend(more.into_iter().flatten());
+        }
+    }
+
+    // Private functions
+    pub(crate) fn from_inner(data: NonNull<ArcData<T, A>>) -> Self {
+        Self { data }
+    }
+
+    /// The inverse of `from_inner`: hands this `Arc<T>`'s strong reference over to whoever keeps the pointer.
+    pub(crate) fn into_inner_ptr(this: Self) -> NonNull<ArcData<T, A>> {
+        core::mem::ManuallyDrop::new(this).data
+    }
+
+    fn data(&self) -> &ArcData<T, A> {
+        unsafe { self.data.as_ref() }
+    }
+
+    fn data_mut(&mut self) -> &mut ArcData<T, A> {
+        unsafe { self.data.as_mut() }
+    }
+}
+
+/// Data whose pointers [`Arc::into_raw_parts`] can split into an address and [`RawParts::Metadata`] (and
+/// [`Arc::from_raw_parts`] can put back together).
+///
+/// Without the `nightly` feature, that's any sized type, as well as slices and `str`s. With it, it's every type,
+/// including trait objects.
+///
+/// This trait is sealed: it can't be implemented outside of this crate.
+pub trait RawParts: raw_parts::Sealed {
+    /// Whatever a pointer to `Self` carries besides the address: nothing (`()`) for sized types, the length for
+    /// slices and `str`s, and (with the `nightly` feature) the [`Pointee::Metadata`](core::ptr::Pointee) of
+    /// anything else, like the vtable of a trait object.
+    type Metadata: Copy;
+
+    #[doc(hidden)]
+    fn split(ptr: *const Self) -> (*const (), Self::Metadata);
+
+    #[doc(hidden)]
+    fn join(data: *const (), metadata: Self::Metadata) -> *const Self;
+}
+
+mod raw_parts {
+    pub trait Sealed {}
+
+    impl<T: ?Sized> Sealed for T {}
+}
+
+#[cfg(not(feature = "nightly"))]
+impl<T> RawParts for T {
+    type Metadata = ();
+
+    fn split(ptr: *const Self) -> (*const (), ()) {
+        (ptr.cast(), ())
+    }
+
+    fn join(data: *const (), (): ()) -> *const Self {
+        data.cast()
+    }
+}
+
+#[cfg(not(feature = "nightly"))]
+impl<T> RawParts for [T] {
+    type Metadata = usize;
+
+    fn split(ptr: *const Self) -> (*const (), usize) {
+        (ptr.cast(), ptr.len())
+    }
+
+    fn join(data: *const (), len: usize) -> *const Self {
+        core::ptr::slice_from_raw_parts(data.cast(), len)
+    }
+}
+
+#[cfg(not(feature = "nightly"))]
+impl RawParts for str {
+    type Metadata = usize;
+
+    fn split(ptr: *const Self) -> (*const (), usize) {
+        <[u8] as RawParts>::split(ptr as *const [u8])
+    }
+
+    fn join(data: *const (), len: usize) -> *const Self {
+        // A pointer to a `str` carries its length in bytes, just like for a `[u8]`.
+        <[u8] as RawParts>::join(data, len) as *const str
+    }
+}
+
+// The same metadata as above for sized types, slices and `str`s, so enabling the feature only adds types.
+#[cfg(feature = "nightly")]
+impl<T: ?Sized> RawParts for T {
+    type Metadata = <T as core::ptr::Pointee>::Metadata;
+
+    fn split(ptr: *const Self) -> (*const (), Self::Metadata) {
+        ptr.to_raw_parts()
+    }
+
+    fn join(data: *const (), metadata: Self::Metadata) -> *const Self {
+        core::ptr::from_raw_parts(data, metadata)
+    }
+}
+
+/// Data that [`Arc::make_mut`] can clone into an allocation of its own: anything that's [`Clone`], as well as
+/// slices of such things and `str`s.
+///
+/// This trait is sealed: it can't be implemented outside of this crate.
+pub trait MakeMut: make_mut::Sealed {}
+
+impl<T: ?Sized + make_mut::Sealed> MakeMut for T {}
+
+mod make_mut {
+    use super::*;
+
+    pub trait Sealed {
+        /// Clones the data into a new `Arc<Self>`. If that panics, nothing is leaked.
+        fn clone_to_arc(&self) -> Arc<Self>;
+
+        /// Moves the data behind `ptr` into a new `Arc<Self>`.
+        ///
+        /// # Safety
+        /// `ptr` must point to valid data, which must not be used (nor dropped) afterwards.
+        unsafe fn move_to_arc(ptr: *const Self) -> Arc<Self>;
+    }
+
+    impl<T: Clone> Sealed for T {
+        fn clone_to_arc(&self) -> Arc<Self> {
+            Arc::new(self.clone())
+        }
+
+        unsafe fn move_to_arc(ptr: *const Self) -> Arc<Self> {
+            Arc::new(unsafe { ptr.read() })
+        }
+    }
+
+    impl<T: Clone> Sealed for [T] {
+        fn clone_to_arc(&self) -> Arc<Self> {
+            Arc::from(self)
+        }
+
+        unsafe fn move_to_arc(ptr: *const Self) -> Arc<Self> {
+            let data = ArcData::allocate_slice(ptr.len(), false);
+            unsafe {
+                let elements = (&raw mut (*data.as_ptr()).data).cast::<T>();
+                core::ptr::copy_nonoverlapping(ptr.cast::<T>(), elements, ptr.len());
+            }
+            Arc::from_inner(data)
+        }
+    }
+
+    impl Sealed for str {
+        fn clone_to_arc(&self) -> Arc<Self> {
+            Arc::from(self)
+        }
+
+        unsafe fn move_to_arc(ptr: *const Self) -> Arc<Self> {
+            // SAFETY: The bytes come from a `str`, so they are valid UTF-8.
+            unsafe { Arc::from_utf8_unchecked(<[u8]>::move_to_arc(ptr as *const [u8])) }
+        }
+    }
+}
+
+impl<T: ?Sized> Arc<T> {
+    /// Turns this `Arc<T>` into a reference to part of its data (like one of its fields, or a subslice), which
+    /// still keeps the whole allocation alive.
+    ///
+    /// Unlike [`Arc::map`], this doesn't touch the data: the projection holds on to the `Arc<T>`, and only
+    /// dereferences to the part returned by `f`.
+    ///
+    /// # Arguments
+    /// * `this` - The `Arc<T>` to consume.
+    /// * `f` - Returns the part of the data the projection dereferences to.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let arc = Arc::new((String::from("hello"), vec![1, 2, 3, 4]));
+    /// let tail = Arc::project(arc.clone(), |pair| &pair.1[2..]);
+    /// drop(arc);
+    /// assert_eq!(*tail, [3, 4]);
+    /// ```
+    pub fn project<U: ?Sized>(this: Self, f: impl FnOnce(&T) -> &U) -> ArcProjection<T, U> {
+        ArcProjection::new(this, f)
+    }
+
+    /// Returns a mutable reference to the data, cloning it into a new allocation first if there are other
+    /// references to it (copy-on-write).
+    ///
+    /// This works for any `T: Clone`, and for slices of them and `str`s (see [`MakeMut`]). If cloning (an element
+    /// of) the data panics, `this` is left as it was.
+    ///
+    /// If this is the only `Arc<T>` but there are [`Weak`]s, the data is moved (not cloned) into a new
+    /// allocation instead, and the [`Weak`]s can no longer upgrade. An empty slice or `str` made by `Default` is
+    /// shared with all the others, so it's always replaced by a new allocation.
+    ///
+    /// # Arguments
+    /// * `this` - A mutable reference to an `Arc<T>`.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let mut arc = Arc::new(41);
+    /// let clone = arc.clone();
+    /// *Arc::make_mut(&mut arc) += 1; // Clones the data, `clone` is left alone
+    /// assert_eq!((*arc, *clone), (42, 41));
+    ///
+    /// let mut frame: Arc<[u8]> = Arc::from(vec![0; 4]);
+    /// let shown = frame.clone();
+    /// Arc::make_mut(&mut frame)[0] = 255;
+    /// assert_eq!((&*frame, &*shown), (&[255, 0, 0, 0][..], &[0; 4][..]));
+    ///
+    /// let mut name: Arc<str> = Arc::from("hello");
+    /// Arc::make_mut(&mut name).make_ascii_uppercase(); // The only reference, so nothing is cloned
+    /// assert_eq!(&*name, "HELLO");
+    /// ```
+    pub fn make_mut(this: &mut Self) -> &mut T
+    where
+        T: MakeMut,
+    {
+        // Setting the strong count to 0 "locks" the data against `Weak::upgrade` (which never increments a 0),
+        // just like in `try_unwrap`. The acquire on success synchronizes with the release decrement of any
+        // dropped `Arc<T>`s, so they are done with the data.
+        if this
+            .data()
+            .refs
+            .compare_exchange(1, 0, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            // There are other `Arc<T>`s, so clone the data into a fresh allocation. Assigning drops our old
+            // `Arc<T>`, decrementing the old allocation's strong count.
+            *this = T::clone_to_arc(this);
+        } else if this.data().weak.load(Ordering::Relaxed) != 1 {
+            // We were the only `Arc<T>`, but `Weak<T>`s exist. They must keep seeing the old allocation (and won't
+            // be able to upgrade anymore), so move the data into a new one (no clone needed).
+            // This `Weak<T>` takes over the implicit weak reference the old allocation's `Arc<T>`s held, and frees
+            // the old allocation when it's dropped if the other `Weak<T>`s are gone by then.
+            let old = Weak { data: this.data };
+            // SAFETY: The strong count is 0 now, so nothing else will read or drop the data (or the finalizer).
+            let data = unsafe { T::move_to_arc(ArcData::data_ptr(this.data)) };
+            let finalizer = unsafe { (*this.data().finalizer.get()).take() };
+            // Overwrite `this` without dropping it, since we already took care of its strong count.
+            unsafe { core::ptr::write(this, data) };
+            // The finalizer moves along with the data.
+            unsafe { *this.data().finalizer.get() = finalizer };
+            drop(old);
+        } else {
+            // We were the only reference of either kind, so just restore the strong count. Release makes sure
+            // anything we did "happens before" the next acquire on the strong count (e.g. in `drop`).
+            this.data().refs.store(1, Ordering::Release);
+        }
+        // Whichever branch we took, `this` is now the only reference to its allocation.
+        this.data_mut().data.get_mut()
+    }
+
+    /// Consumes this `Arc<T>` and returns a raw pointer to its data (see [`Arc::as_ptr`]), without changing
+    /// the strong count. Use [`Arc::from_raw`] to turn it back into an `Arc<T>`, or the data will never be
+    /// dropped (and the allocation will be leaked).
+    ///
+    /// # Arguments
+    /// * `this` - The `Arc<T>` to consume.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let ptr = Arc::into_raw(Arc::new(42));
+    /// assert_eq!(unsafe { *ptr }, 42);
+    /// let arc = unsafe { Arc::from_raw(ptr) };
+    /// assert_eq!(Arc::strong_count(&arc), 1);
+    /// ```
+    pub fn into_raw(this: Self) -> *const T {
+        let ptr = Self::as_ptr(&this);
+        core::mem::forget(this);
+        ptr
+    }
+
+    /// Like [`Arc::into_raw`], but returns the pointer to the data split into its address and its metadata (like
+    /// the length of a slice), which can be passed around separately, e.g. through FFI that only takes thin
+    /// pointers. Use [`Arc::from_raw_parts`] to turn them back into an `Arc<T>`.
+    ///
+    /// # Arguments
+    /// * `this` - The `Arc<T>` to consume.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let (data, len) = Arc::into_raw_parts(Arc::<[u8]>::from(vec![1, 2, 3]));
+    /// assert_eq!(len, 3);
+    /// let arc = unsafe { Arc::<[u8]>::from_raw_parts(data, len) };
+    /// assert_eq!(*arc, [1, 2, 3]);
+    /// ```
+    pub fn into_raw_parts(this: Self) -> (*const (), T::Metadata)
+    where
+        T: RawParts,
+    {
+        T::split(Self::into_raw(this))
+    }
+
+    /// Turns the parts returned by [`Arc::into_raw_parts`] back into an `Arc<T>`, taking over the strong reference
+    /// they stood for.
+    ///
+    /// # Arguments
+    /// * `data` - The address returned by [`Arc::into_raw_parts`].
+    /// * `metadata` - The metadata returned along with it.
+    ///
+    /// # Safety
+    /// Both must have come from the same call to [`Arc::into_raw_parts`] on an `Arc<T>` (with the same `T`), and
+    /// each such call must be balanced by exactly one call to `from_raw_parts`.
+    pub unsafe fn from_raw_parts(data: *const (), metadata: T::Metadata) -> Self
+    where
+        T: RawParts,
+    {
+        Self::from_inner(unsafe { ArcData::from_unsized_data_ptr(T::join(data, metadata)) })
+    }
+}
+
+impl<T> Arc<T> {
+    /// Turns a raw pointer obtained from [`Arc::into_raw`] back into an `Arc<T>`, taking over the strong
+    /// reference it stood for.
+    ///
+    /// # Arguments
+    /// * `ptr` - A pointer returned by [`Arc::into_raw`].
+    ///
+    /// # Safety
+    /// `ptr` must have come from [`Arc::into_raw`] on an `Arc<T>` (with the same `T`), and each call to
+    /// [`Arc::into_raw`] must be balanced by exactly one call to `from_raw`.
+    pub unsafe fn from_raw(ptr: *const T) -> Self {
+        Self::from_inner(unsafe { ArcData::from_data_ptr(ptr) })
+    }
+
+    /// Increments the strong count of the `Arc<T>` behind `ptr`, as if it had been cloned.
+    ///
+    /// The new strong reference is represented by `ptr` itself, so it must eventually be released again, for
+    /// example with [`Arc::from_raw`] or [`Arc::decrement_strong_count`].
+    ///
+    /// # Arguments
+    /// * `ptr` - A pointer returned by [`Arc::into_raw`] or [`Arc::as_ptr`].
+    ///
+    /// # Safety
+    /// `ptr` must have come from [`Arc::into_raw`] or [`Arc::as_ptr`] on an `Arc<T>` (with the same `T`), and
+    /// that allocation must still have a strong count of at least 1 for the duration of this call.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let ptr = Arc::into_raw(Arc::new(42));
+    /// unsafe { Arc::increment_strong_count(ptr) };
+    /// let arc = unsafe { Arc::from_raw(ptr) };
+    /// assert_eq!(Arc::strong_count(&arc), 2);
+    /// unsafe { Arc::decrement_strong_count(ptr) };
+    /// assert_eq!(Arc::strong_count(&arc), 1);
+    /// ```
+    pub unsafe fn increment_strong_count(ptr: *const T) {
+        // Borrow the `Arc<T>` the pointer stands for and clone it (with the same overflow check as always),
+        // without dropping either of them.
+        let arc = ManuallyDrop::new(unsafe { Self::from_raw(ptr) });
+        let _clone = ManuallyDrop::new(Self::clone(&arc));
+    }
+
+    /// Decrements the strong count of the `Arc<T>` behind `ptr`, as if it had been dropped. If this was the last
+    /// strong reference, the data is dropped (and the allocation freed if there are no [`Weak`]s).
+    ///
+    /// # Arguments
+    /// * `ptr` - A pointer returned by [`Arc::into_raw`] or [`Arc::as_ptr`].
+    ///
+    /// # Safety
+    /// `ptr` must have come from [`Arc::into_raw`] or [`Arc::as_ptr`] on an `Arc<T>` (with the same `T`), and
+    /// the strong reference being released must actually be owned by the caller (e.g. one obtained through
+    /// [`Arc::into_raw`] or [`Arc::increment_strong_count`]).
+    pub unsafe fn decrement_strong_count(ptr: *const T) {
+        // Taking over the strong reference and dropping it runs the usual `Drop` logic.
+        drop(unsafe { Self::from_raw(ptr) });
+    }
+
+    /// Replaces the data with `value` and returns the old data, if this is the only reference (see
+    /// [`Arc::get_mut`]). Otherwise, the data is left alone, `value` is dropped, and [`Option::None`] is returned.
+    ///
+    /// # Arguments
+    /// * `this` - A mutable reference to an `Arc<T>`.
+    /// * `value` - The new data.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let mut arc = Arc::new(String::from("old"));
+    /// assert_eq!(Arc::replace(&mut arc, String::from("new")).as_deref(), Some("old"));
+    /// assert_eq!(*arc, "new");
+    ///
+    /// let clone = arc.clone();
+    /// assert_eq!(Arc::replace(&mut arc, String::from("newer")), None);
+    /// assert_eq!(*clone, "new");
+    /// ```
+    pub fn replace(this: &mut Self, value: T) -> Option<T> {
+        // `get_mut` also refuses if there are `Weak<T>`s, which could otherwise upgrade and see the swap.
+        Self::get_mut(this).map(|data| core::mem::replace(data, value))
+    }
+
+    /// Moves the data out and leaves [`Default::default`] in its place, if this is the only reference (see
+    /// [`Arc::get_mut`]). Otherwise, returns [`Option::None`] without touching the data.
+    ///
+    /// # Arguments
+    /// * `this` - A mutable reference to an `Arc<T>`.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let mut arc = Arc::new(vec![4, 2]);
+    /// assert_eq!(Arc::take(&mut arc), Some(vec![4, 2]));
+    /// assert!(arc.is_empty());
+    ///
+    /// let weak = Arc::downgrade(&arc);
+    /// assert_eq!(Arc::take(&mut arc), None);
+    /// # drop(weak);
+    /// ```
+    pub fn take(this: &mut Self) -> Option<T>
+    where
+        T: Default,
+    {
+        Self::get_mut(this).map(core::mem::take)
+    }
+
+    /// Returns the inner data if this is the only `Arc<T>` pointing to it, without dropping or cloning it.
+    /// Otherwise, returns an [`Result::Err`] containing the same `Arc<T>` that was passed in.
+    ///
+    /// Outstanding [`Weak`]s don't prevent this from succeeding; they just won't be able to upgrade afterwards.
+    ///
+    /// # Arguments
+    /// * `this` - The `Arc<T>` to unwrap.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let arc = Arc::new(42);
+    /// assert_eq!(Arc::try_unwrap(arc).ok(), Some(42));
+    ///
+    /// let arc = Arc::new(42);
+    /// let clone = arc.clone();
+    /// let arc = Arc::try_unwrap(arc).unwrap_err();
+    /// assert_eq!(*arc, 42);
+    /// ```
+    pub fn try_unwrap(this: Self) -> Result<T, Self> {
+        // Going from 1 straight to 0 in a single atomic step means no other `Arc<T>` can exist (or be created by
+        // `Weak::upgrade`, which never increments a 0) while we take the data out.
+        if this
+            .data()
+            .refs
+            .compare_exchange(1, 0, Ordering::Relaxed, Ordering::Relaxed)
+            .is_err()
+        {
+            return Err(this);
+        }
+        // Same as in `drop`: every release-decrement by the other (now dropped) `Arc<T>`s "happens before" this
+        // fence, so their uses of the data are done before we move it out.
+        fence(Ordering::Acquire);
+
+        // We're effectively dropping `this` by hand, so don't let its `Drop` run (it would drop the data again).
+        let this = ManuallyDrop::new(this);
+        // SAFETY: The strong count is 0 now, so nothing else will read or drop the data.
+        let data = unsafe { ManuallyDrop::take(&mut *this.data().data.get()) };
+        // Give back the implicit weak reference held by the `Arc<T>`s, which frees the allocation if there are
+        // no `Weak<T>`s left.
+        drop(Weak { data: this.data });
+        Ok(data)
+    }
+
+    /// Returns the inner data if this is the only `Arc<T>` pointing to it, without cloning it. Otherwise, returns a
+    /// clone of the data and drops this `Arc<T>`.
+    ///
+    /// Uniqueness is decided by the same single atomic step as [`Arc::try_unwrap`], not by checking the count
+    /// first and cloning afterwards, so the data is never cloned once every other `Arc<T>` has been dropped.
+    ///
+    /// # Arguments
+    /// * `this` - The `Arc<T>` to unwrap.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let arc = Arc::new(vec![4, 2]);
+    /// let clone = arc.clone();
+    /// assert_eq!(Arc::unwrap_or_clone(arc), vec![4, 2]); // Cloned
+    /// assert_eq!(Arc::unwrap_or_clone(clone), vec![4, 2]); // Moved out
+    /// ```
+    pub fn unwrap_or_clone(this: Self) -> T
+    where
+        T: Clone,
+    {
+        // `try_unwrap` takes care of moving the data out without dropping it twice.
+        Self::try_unwrap(this).unwrap_or_else(|arc| T::clone(&arc))
+    }
+
+    /// Turns an `Arc<T>` into an `Arc<U>` by applying `f` to the data. If this is the only reference to the data
+    /// (counting [`Weak`]s too) and an `ArcData<U>` is laid out like an `ArcData<T>`, the data is transformed in
+    /// place, reusing the allocation. Otherwise, `f` is applied to a clone of the data (like
+    /// [`Arc::unwrap_or_clone`] would return) and the result is stored in a new allocation.
+    ///
+    /// Like other functions moving the data out, this drops a finalizer set by [`Arc::new_with_finalizer`]
+    /// without calling it.
+    ///
+    /// If `f` panics, the data it was given is dropped by the unwinding as usual, and the allocation (if reused)
+    /// is freed.
+    ///
+    /// # Arguments
+    /// * `this` - The `Arc<T>` to consume.
+    /// * `f` - Turns the data into the data of the returned `Arc<U>`.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let raw = Arc::new(String::from("  port = 8080  "));
+    /// let trimmed: Arc<String> = Arc::map(raw, |raw| raw.trim().to_owned());
+    /// assert_eq!(*trimmed, "port = 8080");
+    ///
+    /// // `u32` fits right where the `[u8; 4]` was.
+    /// let bytes = Arc::new([1u8, 0, 0, 0]);
+    /// let ptr = Arc::as_ptr(&bytes).cast::<u8>();
+    /// let number = Arc::map(bytes, u32::from_le_bytes);
+    /// assert_eq!(*number, 1);
+    /// assert_eq!(Arc::as_ptr(&number).cast::<u8>(), ptr);
+    /// ```
+    pub fn map<U>(this: Self, f: impl FnOnce(T) -> U) -> Arc<U>
+    where
+        T: Clone,
+    {
+        if Layout::new::<ArcData<T>>() != Layout::new::<ArcData<U>>() || !Self::is_unique(&this) {
+            return Arc::new(f(Self::unwrap_or_clone(this)));
+        }
+        // Nothing else references the allocation (and nothing else can start to, without an `Arc<T>` or
+        // `Weak<T>` to go through), so we can do as we please with it. The counts are left at 1 throughout.
+        let this = ManuallyDrop::new(this);
+        // SAFETY: As above, this is the only reference, and the finalizer is for a `T` that's about to be gone.
+        drop(unsafe { (*this.data().finalizer.get()).take() });
+        // SAFETY: The data is moved out here, and the `ArcData<T>` is never used as one again.
+        let data = unsafe { ManuallyDrop::take(&mut *this.data().data.get()) };
+
+        // If `f` panics, free the allocation. Dropping a `Weak<T>` does that without touching the (moved out)
+        // data, since the weak count is 1.
+        let guard = Weak { data: this.data };
+        let mapped = f(data);
+        core::mem::forget(guard);
+
+        // SAFETY: The layouts are the same, so the header is where it was, and the data goes where the old data
+        // was.
+        let ptr = this.data.cast::<ArcData<U>>();
+        unsafe {
+            (&raw mut (*ptr.as_ptr()).data).write(UnsafeCell::new(ManuallyDrop::new(mapped)))
+        };
+        Arc::from_inner(ptr)
+    }
+
+    /// Returns the inner data if this was the last `Arc<T>` pointing to it. Otherwise, drops this `Arc<T>` and
+    /// returns [`Option::None`].
+    ///
+    /// Unlike [`Arc::try_unwrap`], this never hands the `Arc<T>` back, so if several threads call `into_inner`
+    /// on their clones at the same time, exactly one of them gets the data.
+    ///
+    /// # Arguments
+    /// * `this` - The `Arc<T>` to consume.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let arc = Arc::new(42);
+    /// let clone = arc.clone();
+    /// assert_eq!(Arc::into_inner(arc), None);
+    /// assert_eq!(Arc::into_inner(clone), Some(42));
+    /// ```
+    pub fn into_inner(this: Self) -> Option<T> {
+        // We decrement the count ourselves below, so `this` must not be dropped the usual way.
+        let this = ManuallyDrop::new(this);
+        // This is the same decrement as in `drop`, and like there, the decrement and the "was I the last one"
+        // check are a single atomic step, so exactly one `Arc<T>` can ever see the 1.
+        let n = this.data().refs.fetch_sub(1, Ordering::Release);
+        if n != 1 {
+            #[cfg(feature = "std")]
+            if n == WAITING | 2 {
+                unsafe { ArcData::wake_waiter(this.data) };
+            }
+            return None;
+        }
+        fence(Ordering::Acquire);
+
+        // SAFETY: The strong count is 0 now, so nothing else will read or drop the data.
+        let data = unsafe { ManuallyDrop::take(&mut *this.data().data.get()) };
+        // Give back the implicit weak reference held by the `Arc<T>`s. If `Weak<T>`s remain, the allocation stays
+        // around for them (and they can't upgrade anymore).
+        drop(Weak { data: this.data });
+        Some(data)
+    }
+
+    /// Blocks the current thread until this is the only `Arc<T>` pointing to the data, then returns the data like
+    /// [`Arc::try_unwrap`] would. If that doesn't happen within `timeout` (if given), gives the `Arc<T>` back as
+    /// an [`Result::Err`] instead.
+    ///
+    /// The thread is parked while waiting (it doesn't spin), and woken up by whichever `Arc<T>` brings the strong
+    /// count down to 1. [`Weak`]s don't keep this from returning, but an `Arc<T>` upgraded from one has to be
+    /// dropped as well. Only one thread can wait on the same data at a time: if another one already is, this
+    /// returns [`Result::Err`] right away (the two would otherwise wait for each other forever).
+    ///
+    /// # Arguments
+    /// * `this` - The `Arc<T>` to unwrap.
+    /// * `timeout` - How long to wait at most, or [`Option::None`] to wait as long as it takes.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// use std::time::Duration;
+    ///
+    /// let state = Arc::new(vec![1, 2, 3]);
+    /// let worker = {
+    ///     let state = state.clone();
+    ///     std::thread::spawn(move || state.iter().sum::<i32>())
+    /// };
+    /// // Returns as soon as the worker's clone is gone, without having to join it first.
+    /// let state = Arc::into_inner_blocking(state, None).unwrap();
+    /// assert_eq!(state, [1, 2, 3]);
+    /// assert_eq!(worker.join().unwrap(), 6);
+    ///
+    /// let arc = Arc::new(42);
+    /// let clone = arc.clone();
+    /// let arc = Arc::into_inner_blocking(arc, Some(Duration::from_millis(10))).unwrap_err();
+    /// assert_eq!(Arc::strong_count(&clone), 2);
+    /// # drop(arc);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn into_inner_blocking(this: Self, timeout: Option<Duration>) -> Result<T, Self> {
+        // A deadline too far away to be represented is as good as none.
+        let deadline = timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+        let current = thread::current();
+        let mut this = this;
+        loop {
+            let data = this.data();
+            if data
+                .waiter
+                .compare_exchange(
+                    core::ptr::null_mut(),
+                    (&raw const current).cast_mut(),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                )
+                .is_err()
+            {
+                // Some other thread is waiting already.
+                return Err(this);
+            }
+            // From here on, whichever `Arc<T>` brings the count down to 1 (not counting the flag) wakes us up and
+            // clears the flag (see `WAITING`). Release makes sure it finds `current` when it does.
+            let n = data.refs.fetch_or(WAITING, Ordering::Release);
+            if n == 1 {
+                // We're the only `Arc<T>` already, so nobody will clear the flag for us. Nobody else can change
+                // the count in the meantime either (`Weak::upgrade` waits for the flag to be cleared first).
+                data.refs.fetch_and(!WAITING, Ordering::Relaxed);
+            }
+            let mut timed_out = false;
+            loop {
+                // Acquire pairs with the release in `wake_waiter`, so it's done with the allocation.
+                let n = data.refs.load(Ordering::Acquire);
+                if n & WAITING == 0 {
+                    break;
+                }
+                // Woken up by `wake_waiter`, spuriously, or by the timeout. Either way, check again.
+                match deadline.map(|deadline| deadline.saturating_duration_since(Instant::now())) {
+                    None => thread::park(),
+                    Some(remaining) if !remaining.is_zero() => thread::park_timeout(remaining),
+                    // Timed out, so clear the flag ourselves, unless the last other `Arc<T>` is being dropped
+                    // right now. Then we have to let it finish (it unparks us once it has).
+                    Some(_) if n == WAITING | 1 => thread::park(),
+                    Some(_) => {
+                        // Fails if the count changed since the load, in which case we look at it again.
+                        if data
+                            .refs
+                            .compare_exchange(n, n & !WAITING, Ordering::Relaxed, Ordering::Relaxed)
+                            .is_ok()
+                        {
+                            timed_out = true;
+                            break;
+                        }
+                    }
+                }
+            }
+            // With the flag cleared, nothing reads `current` anymore.
+            data.waiter.store(core::ptr::null_mut(), Ordering::Relaxed);
+
+            // A `Weak<T>` may have been upgraded since the count dropped to 1, in which case `try_unwrap` fails
+            // and we wait again (for the upgraded `Arc<T>` to be dropped).
+            this = match Self::try_unwrap(this) {
+                Ok(data) => return Ok(data),
+                Err(this) if timed_out => return Err(this),
+                Err(this) => this,
+            };
+        }
+    }
+}
+
+impl<T> Arc<MaybeUninit<T>> {
+    /// Converts to an `Arc<T>`, reusing the same allocation as is (nothing is copied, and the reference counts
+    /// carry over unchanged).
+    ///
+    /// # Safety
+    /// The data must have been initialized. Since all clones (and [`Weak`]s) share it, they are turned into
+    /// `Arc<T>`s by this too, as far as the data is concerned.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let mut arc = Arc::<String>::new_uninit();
+    /// Arc::get_mut(&mut arc).unwrap().write(String::from("hello"));
+    /// let arc = unsafe { arc.assume_init() };
+    /// assert_eq!(*arc, "hello");
+    /// ```
+    pub unsafe fn assume_init(self) -> Arc<T> {
+        // `ArcData` is `repr(C)`, so an `ArcData<MaybeUninit<T>>` has the same layout as an `ArcData<T>`. Our
+        // strong reference is handed over to the new `Arc<T>`, so don't let `self` give it back.
+        let this = ManuallyDrop::new(self);
+        Arc::from_inner(this.data.cast())
+    }
+}
+
+impl<T> Arc<[T]> {
+    /// Creates a new `Arc<[MaybeUninit<T>]>` with room for `len` elements, which can then be written in place
+    /// (e.g. through [`Arc::get_mut`]) before calling [`Arc::assume_init`].
+    ///
+    /// # Panics
+    /// Panics if the allocation would be larger than `isize::MAX` bytes.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let mut arc = Arc::<[u32]>::new_uninit_slice(3);
+    /// for (i, x) in Arc::get_mut(&mut arc).unwrap().iter_mut().enumerate() {
+    ///     x.write(i as u32 * 2);
+    /// }
+    /// let arc = unsafe { arc.assume_init() };
+    /// assert_eq!(*arc, [0, 2, 4]);
+    /// ```
+    pub fn new_uninit_slice(len: usize) -> Arc<[MaybeUninit<T>]> {
+        Arc::from_inner(ArcData::allocate_slice(len, false))
+    }
+
+    /// Like [`Arc::new_uninit_slice`], but the elements are filled with zero bytes (see [`Arc::new_zeroed`]).
+    ///
+    /// # Panics
+    /// Panics if the allocation would be larger than `isize::MAX` bytes.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let arc = unsafe { Arc::<[u64]>::new_zeroed_slice(1024).assume_init() };
+    /// assert!(arc.iter().all(|&x| x == 0));
+    /// ```
+    pub fn new_zeroed_slice(len: usize) -> Arc<[MaybeUninit<T>]> {
+        Arc::from_inner(ArcData::allocate_slice(len, true))
+    }
+}
+
+impl<T> Arc<[MaybeUninit<T>]> {
+    /// Converts to an `Arc<[T]>`, reusing the same allocation as is (like [`Arc::assume_init`] for a single
+    /// `MaybeUninit<T>`).
+    ///
+    /// # Safety
+    /// Every element must have been initialized.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let mut arc = Arc::<[String]>::new_uninit_slice(2);
+    /// let elements = Arc::get_mut(&mut arc).unwrap();
+    /// elements[0].write(String::from("4"));
+    /// elements[1].write(String::from("2"));
+    /// let arc = unsafe { arc.assume_init() };
+    /// assert_eq!(*arc, ["4", "2"]);
+    /// ```
+    pub unsafe fn assume_init(self) -> Arc<[T]> {
+        // A `MaybeUninit<T>` has the same layout as a `T`, so an `ArcData<[MaybeUninit<T>]>` has the same layout as
+        // an `ArcData<[T]>` of the same length (the pointer metadata). Our strong reference is handed over.
+        let this = ManuallyDrop::new(self);
+        Arc::from_inner(unsafe { NonNull::new_unchecked(this.data.as_ptr() as *mut ArcData<[T]>) })
+    }
+}
+
+impl<T: Clone> Arc<[T]> {
+    /// Creates a new `Arc<[T]>` holding `n` copies of `elem`, like `vec![elem; n]`.
+    ///
+    /// The elements are written straight into a single allocation: `elem` is cloned for all but the last one,
+    /// which gets `elem` itself. If a clone panics, the clones made so far are dropped and the allocation is
+    /// freed. For `n == 0`, `elem` is dropped, and the result is an empty `Arc<[T]>::default()` (which usually
+    /// doesn't allocate).
+    ///
+    /// # Arguments
+    /// * `elem` - The value to fill the slice with.
+    /// * `n` - The length of the slice.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let zeros = Arc::from_elem(0u8, 1024);
+    /// assert_eq!(zeros.len(), 1024);
+    /// assert!(zeros.iter().all(|&x| x == 0));
+    /// ```
+    pub fn from_elem(elem: T, n: usize) -> Self {
+        if n == 0 {
+            return Self::default();
+        }
+        // If a `clone` panics, dropping the builder drops the clones made so far and frees the allocation (and
+        // `elem` is dropped as usual).
+        let mut builder = SliceBuilder::new(n);
+        for _ in 1..n {
+            builder.push(elem.clone());
+        }
+        builder.push(elem);
+        Self::from_inner(builder.finish())
+    }
+}
+
+impl<T: Copy> Arc<[T]> {
+    // Private functions
+    /// Copies the elements of `src` into a new `Arc<[T]>`. Since they're `Copy`, this can't panic halfway through.
+    fn copy_from_slice(src: &[T]) -> Self {
+        let data = ArcData::allocate_slice(src.len(), false);
+        unsafe {
+            let elements = (&raw mut (*data.as_ptr()).data).cast::<T>();
+            core::ptr::copy_nonoverlapping(src.as_ptr(), elements, src.len());
+        }
+        Self::from_inner(data)
+    }
+}
+
+impl Arc<str> {
+    // Private functions
+    /// Turns an `Arc<[u8]>` into an `Arc<str>`, reusing the same allocation.
+    ///
+    /// # Safety
+    /// The bytes must be valid UTF-8.
+    unsafe fn from_utf8_unchecked(bytes: Arc<[u8]>) -> Self {
+        // A `str` has the same layout as a `[u8]`, and pointers to both carry the length in bytes as metadata. Our
+        // strong reference is handed over to the new `Arc<str>`.
+        let bytes = ManuallyDrop::new(bytes);
+        Self::from_inner(unsafe {
+            NonNull::new_unchecked(bytes.data.as_ptr() as *mut ArcData<str>)
+        })
+    }
+}
+
+impl Arc<CStr> {
+    // Private functions
+    /// Turns an `Arc<[u8]>` into an `Arc<CStr>`, reusing the same allocation.
+    ///
+    /// # Safety
+    /// The bytes must end with the only nul byte in them, like for [`CStr::from_bytes_with_nul_unchecked`].
+    unsafe fn from_bytes_with_nul_unchecked(bytes: Arc<[u8]>) -> Self {
+        // A `CStr` is its bytes (nul included), and pointers to it carry their length as metadata, just like for a
+        // `[u8]`. Our strong reference is handed over to the new `Arc<CStr>`.
+        let bytes = ManuallyDrop::new(bytes);
+        let arc = Self::from_inner(unsafe {
+            NonNull::new_unchecked(bytes.data.as_ptr() as *mut ArcData<CStr>)
+        });
+        debug_assert_eq!(arc.to_bytes_with_nul(), &**bytes);
+        arc
+    }
+}
+
+#[cfg(feature = "std")]
+impl Arc<OsStr> {
+    // Private functions
+    /// Turns an `Arc<[u8]>` into an `Arc<OsStr>`, reusing the same allocation.
+    ///
+    /// # Safety
+    /// The bytes must have come from [`OsStr::as_encoded_bytes`] (or [`OsString::into_encoded_bytes`]), like for
+    /// [`OsStr::from_encoded_bytes_unchecked`].
+    unsafe fn from_encoded_bytes_unchecked(bytes: Arc<[u8]>) -> Self {
+        // Whatever the platform, an `OsStr` is its encoded bytes (that's what `as_encoded_bytes` hands out), and
+        // pointers to it carry their length as metadata, just like for a `[u8]`. Our strong reference is handed
+        // over to the new `Arc<OsStr>`.
+        let bytes = ManuallyDrop::new(bytes);
+        let arc = Self::from_inner(unsafe {
+            NonNull::new_unchecked(bytes.data.as_ptr() as *mut ArcData<OsStr>)
+        });
+        debug_assert_eq!(arc.as_encoded_bytes(), &**bytes);
+        arc
+    }
+}
+
+#[cfg(feature = "std")]
+impl Arc<Path> {
+    // Private functions
+    /// Turns an `Arc<OsStr>` into an `Arc<Path>`, reusing the same allocation.
+    fn from_os_str(os_str: Arc<OsStr>) -> Self {
+        // A `Path` is a wrapper around an `OsStr` (`Path::new` only casts the reference), so pointers to both carry
+        // the same metadata. Our strong reference is handed over to the new `Arc<Path>`.
+        let os_str = ManuallyDrop::new(os_str);
+        let arc = Self::from_inner(unsafe {
+            NonNull::new_unchecked(os_str.data.as_ptr() as *mut ArcData<Path>)
+        });
+        debug_assert_eq!(arc.as_os_str(), &**os_str);
+        arc
+    }
+}
+
+impl Arc<dyn Any + Send + Sync> {
+    /// Attempts to downcast to a concrete type, returning the same `Arc` back as an [`Result::Err`] if the data
+    /// isn't a `T`.
+    ///
+    /// Either way, the allocation and the reference counts are left untouched: on success, the returned `Arc<T>`
+    /// simply takes over this strong reference.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// use std::any::Any;
+    ///
+    /// let any: Arc<dyn Any + Send + Sync> = Arc::from(Arc::new(42));
+    /// let any = any.downcast::<String>().unwrap_err();
+    /// assert_eq!(any.downcast::<i32>().ok().as_deref(), Some(&42));
+    /// ```
+    pub fn downcast<T: Any + Send + Sync>(self) -> Result<Arc<T>, Self> {
+        if (*self).is::<T>() {
+            // SAFETY: We just checked that the data is a `T`.
+            Ok(unsafe { self.downcast_unchecked() })
+        } else {
+            Err(self)
+        }
+    }
+
+    /// Downcasts to a concrete type without checking that the data really is a `T`, for when that's already
+    /// known (like in a registry keyed by [`TypeId`](core::any::TypeId)). Like [`Arc::downcast`], this only
+    /// casts the pointer.
+    ///
+    /// # Safety
+    /// The data must be a `T`. Anything else is undefined behavior.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// use std::any::Any;
+    ///
+    /// let any: Arc<dyn Any + Send + Sync> = Arc::from(Arc::new(42));
+    /// // SAFETY: We just put an `i32` in there.
+    /// let arc = unsafe { any.downcast_unchecked::<i32>() };
+    /// assert_eq!(*arc, 42);
+    /// ```
+    pub unsafe fn downcast_unchecked<T: Any + Send + Sync>(self) -> Arc<T> {
+        debug_assert!((*self).is::<T>(), "`downcast_unchecked` to the wrong type");
+        let this = ManuallyDrop::new(self);
+        // Dropping the vtable from the pointer leaves a pointer to the `ArcData<T>` it was created from.
+        Arc::from_inner(this.data.cast::<ArcData<T>>())
+    }
+}
+
+impl<T: ?Sized, A: Allocator> Clone for Arc<T, A> {
+    fn clone(&self) -> Self {
+        // `WAITING` doesn't count (see `into_inner_blocking`).
+        if self.data().refs.fetch_add(1, Ordering::Relaxed) & !WAITING > MAX_REFS {
+            abort();
+        }
+        Self { data: self.data }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        // Already the same allocation: dropping one reference only to take it again would be two wasted atomic
+        // operations.
+        if Self::ptr_eq(self, source) {
+            return;
+        }
+        *self = source.clone();
+    }
+}
+
+/// An iterator handing out clones of an [`Arc`], created by [`Arc::clones`].
+///
+/// It owns one strong reference for each clone it has yet to hand out.
+pub struct Clones<T: ?Sized, A: Allocator = Global> {
+    data: NonNull<ArcData<T, A>>,
+    remaining: usize,
+}
+
+impl<T: ?Sized, A: Allocator> Iterator for Clones<T, A> {
+    type Item = Arc<T, A>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        // The strong reference for this clone was added by `Arc::clones` already.
+        self.remaining -= 1;
+        Some(Arc::from_inner(self.data))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T: ?Sized, A: Allocator> ExactSizeIterator for Clones<T, A> {}
+
+impl<T: ?Sized, A: Allocator> FusedIterator for Clones<T, A> {}
+
+impl<T: ?Sized, A: Allocator> Drop for Clones<T, A> {
+    fn drop(&mut self) {
+        if self.remaining == 0 {
+            return;
+        }
+        // Give back all remaining strong references but one with a single decrement. Since we keep that one, this
+        // can't be the last. Release matches the decrement in `Arc<T>`'s `Drop`.
+        unsafe { self.data.as_ref() }
+            .refs
+            .fetch_sub(self.remaining - 1, Ordering::Release);
+        // The last one may well be the last strong reference overall (if every `Arc<T>` is gone already), so let
+        // `Arc<T>`'s `Drop` deal with it.
+        drop(Arc::from_inner(self.data));
+    }
+}
+
+unsafe impl<T: ?Sized + Send + Sync, A: Allocator + Send + Sync> Send for Clones<T, A> {}
+unsafe impl<T: ?Sized + Send + Sync, A: Allocator + Send + Sync> Sync for Clones<T, A> {}
+
+impl<T: ?Sized, A: Allocator> Drop for Arc<T, A> {
+    fn drop(&mut self) {
+        self.release(|_| ());
+    }
+}
+
+impl<T: ?Sized, A: Allocator> Arc<T, A> {
+    // Private functions
+    /// Gives back this `Arc<T>`'s strong reference, like dropping it. If it was the last one, the data is given to
+    /// `unlink` right before it's dropped (after the finalizer ran).
+    ///
+    /// This `Arc<T>` must not be used (or dropped) afterwards.
+    fn release(&mut self, unlink: impl FnOnce(&mut T)) {
+        // The atomic memory orderings here are only to prevent the compiler from reordering (and maybe some wacky architectures) the drop (not an atomic operation)
+        // before the fetch_sub.
+        //
+        // x86-64, ARM, PowerPC and other architectures that use MESI or MOESI cache coherence protocols already guarantee that even a
+        // relaxed atomic operation will be "immediately" visible to all other cores in the system
+        // (since it had to get the cache line in exclusive mode to perform the operation).
+        let n = self.data().refs.fetch_sub(1, Ordering::Release);
+        #[cfg(feature = "std")]
+        if n == WAITING | 2 {
+            // The one `Arc<T>` left is waiting for this in `into_inner_blocking`.
+            unsafe { ArcData::wake_waiter(self.data) };
+            return;
+        }
+        if n == 1 {
+            // The above release and everything before it "happens before" the following acquire fence and everything after it.
+            fence(Ordering::Acquire);
+            // Now that there are no `Arc<T>`s left, give back the implicit weak reference they collectively held.
+            // Whoever drops the last weak reference (possibly this one) frees the allocation.
+            // This `Weak<T>` is created *before* dropping the data, so that it still gets dropped (and the
+            // allocation doesn't leak) if `T`'s destructor panics.
+            let _weak = Weak { data: self.data };
+            // Only the data is dropped here (after running the finalizer, if any). The allocation itself may still be
+            // referenced by `Weak<T>`s.
+            unsafe { self.data().drop_data(unlink) };
+        }
+    }
+}
+
+/// Creates a new `Arc<T>` containing `T`'s default value.
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// let arc: Arc<i32> = Arc::default();
+/// assert_eq!(*arc, 0);
+/// ```
+impl<T: Default> Default for Arc<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+/// Creates an empty `Arc<[T]>`.
+///
+/// This doesn't allocate: all empty slices made this way share one static allocation (unless `T` needs more
+/// alignment than that has, which is at least 16, in which case each gets its own). The static holds one strong
+/// reference to it that it never gives back, so [`Arc::strong_count`] includes that one, and [`Arc::get_mut`]
+/// always returns `None`.
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// let a: Arc<[i32]> = Arc::default();
+/// let b: Arc<[i32]> = Arc::default();
+/// assert!(a.is_empty());
+/// assert!(Arc::ptr_eq(&a, &b));
+/// ```
+impl<T> Default for Arc<[T]> {
+    fn default() -> Self {
+        match ArcData::static_empty() {
+            // The `ManuallyDrop` stands for the reference the static holds, which is never dropped.
+            Some(data) => (*ManuallyDrop::new(Self::from_inner(data))).clone(),
+            None => Self::from([]),
+        }
+    }
+}
+
+/// Creates an empty `Arc<str>`, sharing the same static allocation as an empty `Arc<[u8]>` (see
+/// [`Arc<[T]>::default`](Arc#impl-Default-for-Arc<[T]>)).
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// let arc: Arc<str> = Arc::default();
+/// assert_eq!(&*arc, "");
+/// ```
+impl Default for Arc<str> {
+    fn default() -> Self {
+        // An empty slice is valid UTF-8.
+        unsafe { Self::from_utf8_unchecked(Arc::default()) }
+    }
+}
+
+/// Creates an empty `Arc<CStr>`, sharing the same static allocation as an empty `Arc<[u8]>` (see
+/// [`Arc<[T]>::default`](Arc#impl-Default-for-Arc<[T]>)), which also holds the nul byte.
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// use core::ffi::CStr;
+/// let arc: Arc<CStr> = Arc::default();
+/// assert_eq!(&*arc, c"");
+/// ```
+impl Default for Arc<CStr> {
+    fn default() -> Self {
+        // As above, the static's own reference isn't handed out.
+        let bytes = (*ManuallyDrop::new(Arc::<[u8]>::from_inner(ArcData::static_nul()))).clone();
+        // The static's data is a single nul byte.
+        unsafe { Self::from_bytes_with_nul_unchecked(bytes) }
+    }
+}
+
+/// Same as [`Arc::new`].
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// let arc: Arc<i32> = 5.into();
+/// assert_eq!(*arc, 5);
+/// ```
+impl<T> From<T> for Arc<T> {
+    fn from(data: T) -> Self {
+        Self::new(data)
+    }
+}
+
+/// Moves the boxed value into a new `Arc<T>` (without cloning or dropping it) and frees the `Box`.
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// let arc: Arc<i32> = Arc::from(Box::new(5));
+/// assert_eq!(*arc, 5);
+/// ```
+#[cfg(not(feature = "nightly"))]
+impl<T> From<Box<T>> for Arc<T> {
+    fn from(boxed: Box<T>) -> Self {
+        // The `Box` has no room for the reference counts, so the value has to move to a new allocation.
+        Self::from_inner(unsafe { ArcData::from_box(boxed, |ptr, _| ptr.cast()) })
+    }
+}
+
+/// Moves the boxed value into a new `Arc<T>` (without cloning or dropping it) and frees the `Box`.
+///
+/// With the `nightly` feature, this works for every `T`, unsized ones like `Box<dyn Error>` included (without
+/// it, only for sized `T`s, slices and `str`s).
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// use std::error::Error;
+/// let boxed: Box<dyn Error + Send + Sync> = "oops".into();
+/// let arc: Arc<dyn Error + Send + Sync> = Arc::from(boxed);
+/// assert_eq!(arc.to_string(), "oops");
+/// ```
+#[cfg(feature = "nightly")]
+impl<T: ?Sized> From<Box<T>> for Arc<T> {
+    fn from(boxed: Box<T>) -> Self {
+        // As above, but the metadata can't just be spelled out, so it's copied over from the `Box`'s pointer.
+        Self::from_inner(unsafe {
+            ArcData::from_box(boxed, |ptr, boxed| {
+                ptr.with_metadata_of(boxed as *mut ArcData<T>)
+            })
+        })
+    }
+}
+
+/// Clones the elements into a new `Arc<[T]>`, which holds the reference counts and the elements in a single
+/// allocation.
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// let arc: Arc<[String]> = Arc::from(&[String::from("hello")][..]);
+/// assert_eq!(*arc, ["hello"]);
+/// ```
+impl<T: Clone> From<&[T]> for Arc<[T]> {
+    fn from(slice: &[T]) -> Self {
+        // If a `clone` panics, dropping the builder drops the clones made so far and frees the allocation.
+        let mut builder = SliceBuilder::new(slice.len());
+        for element in slice {
+            builder.push(element.clone());
+        }
+        Self::from_inner(builder.finish())
+    }
+}
+
+/// Moves the elements into a new `Arc<[T]>` (without cloning them) and frees the `Vec`'s buffer.
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// let arc: Arc<[i32]> = Arc::from(vec![4, 2]);
+/// assert_eq!(*arc, [4, 2]);
+/// ```
+impl<T> From<Vec<T>> for Arc<[T]> {
+    fn from(mut vec: Vec<T>) -> Self {
+        // Like for a `Box`, the `Vec`'s buffer has no room for the reference counts, so the elements have to move.
+        let len = vec.len();
+        let data = ArcData::allocate_slice(len, false);
+        unsafe {
+            let elements = (&raw mut (*data.as_ptr()).data).cast::<T>();
+            core::ptr::copy_nonoverlapping(vec.as_ptr(), elements, len);
+            // The elements belong to the `Arc<[T]>` now, so the `Vec` must only free its buffer, not drop them.
+            vec.set_len(0);
+        }
+        Self::from_inner(data)
+    }
+}
+
+/// Moves the elements into a new `Arc<[T]>` (without cloning them) and frees the `Box`. With the `nightly`
+/// feature, the `From<Box<T>>` above covers this too.
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// let boxed: Box<[String]> = Box::new([String::from("hello")]);
+/// let arc: Arc<[String]> = Arc::from(boxed);
+/// assert_eq!(*arc, ["hello"]);
+/// ```
+#[cfg(not(feature = "nightly"))]
+impl<T> From<Box<[T]>> for Arc<[T]> {
+    fn from(slice: Box<[T]>) -> Self {
+        // The metadata of a pointer to an `ArcData<[T]>` is the length of its slice, just like for a `[T]`.
+        Self::from_inner(unsafe {
+            ArcData::from_box(slice, |ptr, slice| {
+                core::ptr::slice_from_raw_parts_mut(ptr.cast::<T>(), slice.len())
+                    as *mut ArcData<[T]>
+            })
+        })
+    }
+}
+
+/// Clones the borrowed elements (like `From<&[T]>`) or moves the owned ones (like `From<Vec<T>>`) into a new
+/// `Arc<[T]>`.
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// use std::borrow::Cow;
+/// let borrowed: Arc<[i32]> = Arc::from(Cow::Borrowed(&[4, 2][..]));
+/// let owned: Arc<[i32]> = Arc::from(Cow::<[i32]>::Owned(vec![4, 2]));
+/// assert_eq!(*borrowed, *owned);
+/// ```
+impl<T: Clone> From<Cow<'_, [T]>> for Arc<[T]> {
+    fn from(cow: Cow<'_, [T]>) -> Self {
+        match cow {
+            Cow::Borrowed(slice) => Self::from(slice),
+            Cow::Owned(vec) => Self::from(vec),
+        }
+    }
+}
+
+/// Moves the elements into a new `Arc<[T]>` (without cloning them).
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// let arc: Arc<[i32]> = [1, 2, 3].into();
+/// assert_eq!(*arc, [1, 2, 3]);
+/// ```
+impl<T, const N: usize> From<[T; N]> for Arc<[T]> {
+    fn from(array: [T; N]) -> Self {
+        // Allocate first, so that if that unwinds, `array` is still dropped like any other local.
+        let data = ArcData::allocate_slice(N, false);
+        let array = ManuallyDrop::new(array);
+        unsafe {
+            let elements = (&raw mut (*data.as_ptr()).data).cast::<T>();
+            // The elements belong to the `Arc<[T]>` now, so `array` must not drop them (hence the `ManuallyDrop`).
+            core::ptr::copy_nonoverlapping(array.as_ptr(), elements, N);
+        }
+        Self::from_inner(data)
+    }
+}
+
+/// Turns an `Arc<[T]>` with exactly `N` elements into an `Arc<[T; N]>`, keeping the same allocation (and
+/// reference counts). Gives the `Arc<[T]>` back if its length is different.
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// let slice: Arc<[i32]> = Arc::from(vec![4, 2]);
+/// let slice = Arc::<[i32; 3]>::try_from(slice).unwrap_err();
+/// let array: Arc<[i32; 2]> = slice.try_into().ok().unwrap();
+/// assert_eq!(*array, [4, 2]);
+/// ```
+impl<T, const N: usize> TryFrom<Arc<[T]>> for Arc<[T; N]> {
+    type Error = Arc<[T]>;
+
+    fn try_from(slice: Arc<[T]>) -> Result<Self, Arc<[T]>> {
+        if slice.len() != N {
+            return Err(slice);
+        }
+        // A `[T; N]` is laid out just like a `[T]` of length `N`: same alignment, same size, and the elements at
+        // the same offsets. `ArcData` is `repr(C)`, so the header in front of them is the same too, and the data
+        // starts at the same (`T`-aligned) offset. So an `ArcData<[T]>` with `N` elements is laid out just like an
+        // `ArcData<[T; N]>`, and `deallocate` computes the same layout for it once the last `Weak` is gone.
+        // Dropping the length from the pointer leaves a pointer to the latter, which takes over our strong
+        // reference.
+        let this = ManuallyDrop::new(slice);
+        Ok(Arc::from_inner(this.data.cast::<ArcData<[T; N]>>()))
+    }
+}
+
+/// Collects the items into a new `Arc<[T]>`.
+///
+/// If the iterator knows exactly how many items it has left (its [`Iterator::size_hint`] bounds are equal, as for
+/// an [`ExactSizeIterator`]), they are written straight into a single allocation. Otherwise, they are collected
+/// into a `Vec<T>` first. If the iterator turns out to have lied about its size, the items are moved into a
+/// `Vec<T>` too, so the result is always correct.
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// let squares: Arc<[u32]> = (1..=4).map(|x| x * x).collect();
+/// assert_eq!(*squares, [1, 4, 9, 16]);
+/// ```
+impl<T> FromIterator<T> for Arc<[T]> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut iter = iter.into_iter();
+        let (lower, upper) = iter.size_hint();
+        if upper != Some(lower) {
+            return Self::from(iter.collect::<Vec<T>>());
+        }
+
+        // If `next` panics, dropping the builder drops the items collected so far and frees the allocation.
+        let mut builder = SliceBuilder::new(lower);
+        while !builder.is_full() {
+            match iter.next() {
+                Some(item) => builder.push(item),
+                // Fewer items than promised.
+                None => return Self::from(builder.into_vec()),
+            }
+        }
+        match iter.next() {
+            None => Self::from_inner(builder.finish()),
+            // More items than promised.
+            Some(item) => {
+                let mut vec = builder.into_vec();
+                vec.push(item);
+                vec.extend(iter);
+                Self::from(vec)
+            }
+        }
+    }
+}
+
+/// Copies the string into a new `Arc<str>`, which holds the reference counts and the bytes in a single allocation.
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// let arc: Arc<str> = Arc::from("hello");
+/// assert_eq!(&*arc, "hello");
+/// ```
+impl From<&str> for Arc<str> {
+    fn from(s: &str) -> Self {
+        // SAFETY: The bytes come from a `str`, so they are valid UTF-8.
+        unsafe { Self::from_utf8_unchecked(Arc::copy_from_slice(s.as_bytes())) }
+    }
+}
+
+/// Moves the string's bytes into a new `Arc<str>` and frees the `String`'s buffer.
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// let arc: Arc<str> = Arc::from(String::from("hello"));
+/// assert_eq!(&*arc, "hello");
+/// ```
+impl From<String> for Arc<str> {
+    fn from(s: String) -> Self {
+        // SAFETY: The bytes come from a `String`, so they are valid UTF-8.
+        unsafe { Self::from_utf8_unchecked(Arc::from(s.into_bytes())) }
+    }
+}
+
+/// Moves the string's bytes into a new `Arc<str>` and frees the `Box`. With the `nightly` feature, the
+/// `From<Box<T>>` above covers this too.
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// let arc: Arc<str> = Arc::from(Box::<str>::from("hello"));
+/// assert_eq!(&*arc, "hello");
+/// ```
+#[cfg(not(feature = "nightly"))]
+impl From<Box<str>> for Arc<str> {
+    fn from(s: Box<str>) -> Self {
+        // A pointer to a `str` carries its length in bytes, just like for a `[u8]`.
+        Self::from_inner(unsafe {
+            ArcData::from_box(s, |ptr, s| {
+                core::ptr::slice_from_raw_parts_mut(ptr, (s as *mut [u8]).len())
+                    as *mut ArcData<str>
+            })
+        })
+    }
+}
+
+/// Copies the string's bytes into a new `Arc<str>`, from wherever they are (see `From<&str>` and
+/// `From<String>`).
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// use std::borrow::Cow;
+/// let borrowed: Arc<str> = Arc::from(Cow::Borrowed("hello"));
+/// let owned: Arc<str> = Arc::from(Cow::<str>::Owned(String::from("hello")));
+/// assert_eq!(&*borrowed, &*owned);
+/// ```
+impl From<Cow<'_, str>> for Arc<str> {
+    fn from(cow: Cow<'_, str>) -> Self {
+        match cow {
+            Cow::Borrowed(s) => Self::from(s),
+            Cow::Owned(s) => Self::from(s),
+        }
+    }
+}
+
+/// Collects the characters into a new `Arc<str>`.
+///
+/// The length isn't known up front, so they're collected into a `String` first, which is then copied into the
+/// `Arc<str>`'s allocation (see `From<String>`).
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// let arc: Arc<str> = "hello".chars().rev().collect();
+/// assert_eq!(&*arc, "olleh");
+/// ```
+impl FromIterator<char> for Arc<str> {
+    fn from_iter<I: IntoIterator<Item = char>>(iter: I) -> Self {
+        Self::from(iter.into_iter().collect::<String>())
+    }
+}
+
+/// Concatenates the strings into a new `Arc<str>`, collecting them into a `String` first like
+/// `FromIterator<char>`.
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// let parts = vec![String::from("hello"), String::from(", "), String::from("world")];
+/// let arc: Arc<str> = parts.iter().map(|s| s.as_str()).collect();
+/// assert_eq!(&*arc, "hello, world");
+/// ```
+impl<'a> FromIterator<&'a str> for Arc<str> {
+    fn from_iter<I: IntoIterator<Item = &'a str>>(iter: I) -> Self {
+        Self::from(iter.into_iter().collect::<String>())
+    }
+}
+
+/// Concatenates the strings into a new `Arc<str>`, collecting them into a `String` first like
+/// `FromIterator<char>`.
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// let arc: Arc<str> = (1..=3).map(|n| n.to_string()).collect();
+/// assert_eq!(&*arc, "123");
+/// ```
+impl FromIterator<String> for Arc<str> {
+    fn from_iter<I: IntoIterator<Item = String>>(iter: I) -> Self {
+        Self::from(iter.into_iter().collect::<String>())
+    }
+}
+
+/// Turns an `Arc<str>` into an `Arc<[u8]>` of its bytes, without copying them or touching the reference counts.
+///
+/// Both point to the same allocation, which is freed only once the last of them is gone, whichever it is.
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// let s: Arc<str> = Arc::from("hello");
+/// let bytes: Arc<[u8]> = Arc::from(s.clone());
+/// assert_eq!(&*bytes, b"hello");
+/// assert_eq!(bytes.as_ptr(), s.as_ptr());
+/// ```
+impl From<Arc<str>> for Arc<[u8]> {
+    fn from(s: Arc<str>) -> Self {
+        // A `str` has the same layout as a `[u8]`, so the `repr(C)` `ArcData<str>` has the same header, at the same
+        // offsets, as an `ArcData<[u8]>` of the same length, and the allocation's layout (which is what it's freed
+        // with) is the same for both. Pointers to either carry the length in bytes as metadata. Our strong reference is
+        // handed over to the new `Arc<[u8]>`.
+        let s = ManuallyDrop::new(s);
+        Self::from_inner(unsafe { NonNull::new_unchecked(s.data.as_ptr() as *mut ArcData<[u8]>) })
+    }
+}
+
+/// Copies the string (and its nul terminator) into a new `Arc<CStr>`, which holds the reference counts and the
+/// bytes in a single allocation.
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// use std::ffi::CStr;
+/// let arc: Arc<CStr> = Arc::from(c"hello");
+/// assert_eq!(&*arc, c"hello");
+/// ```
+impl From<&CStr> for Arc<CStr> {
+    fn from(s: &CStr) -> Self {
+        // SAFETY: The bytes come from a `CStr`, nul terminator included.
+        unsafe { Self::from_bytes_with_nul_unchecked(Arc::copy_from_slice(s.to_bytes_with_nul())) }
+    }
+}
+
+/// Moves the string's bytes (and its nul terminator) into a new `Arc<CStr>` and frees the `CString`'s buffer.
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// use std::ffi::{CStr, CString};
+/// let arc: Arc<CStr> = Arc::from(CString::new("hello").unwrap());
+/// assert_eq!(&*arc, c"hello");
+/// ```
+impl From<CString> for Arc<CStr> {
+    fn from(s: CString) -> Self {
+        // SAFETY: The bytes come from a `CString`, nul terminator included.
+        unsafe { Self::from_bytes_with_nul_unchecked(Arc::from(s.into_bytes_with_nul())) }
+    }
+}
+
+/// Copies the string into a new `Arc<OsStr>`, which holds the reference counts and the bytes in a single
+/// allocation.
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// use std::ffi::OsStr;
+/// let arc: Arc<OsStr> = Arc::from(OsStr::new("hello"));
+/// assert_eq!(&*arc, "hello");
+/// ```
+#[cfg(feature = "std")]
+impl From<&OsStr> for Arc<OsStr> {
+    fn from(s: &OsStr) -> Self {
+        // SAFETY: The bytes come from `as_encoded_bytes`.
+        unsafe { Self::from_encoded_bytes_unchecked(Arc::copy_from_slice(s.as_encoded_bytes())) }
+    }
+}
+
+/// Moves the string's bytes into a new `Arc<OsStr>` and frees the `OsString`'s buffer.
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// use std::ffi::{OsStr, OsString};
+/// let arc: Arc<OsStr> = Arc::from(OsString::from("hello"));
+/// assert_eq!(&*arc, "hello");
+/// ```
+#[cfg(feature = "std")]
+impl From<OsString> for Arc<OsStr> {
+    fn from(s: OsString) -> Self {
+        // SAFETY: The bytes come from `into_encoded_bytes`.
+        unsafe { Self::from_encoded_bytes_unchecked(Arc::from(s.into_encoded_bytes())) }
+    }
+}
+
+/// Copies the path into a new `Arc<Path>`, which holds the reference counts and the bytes in a single allocation.
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// use std::path::Path;
+/// let arc: Arc<Path> = Arc::from(Path::new("/tmp/hello"));
+/// assert_eq!(arc.file_name().unwrap(), "hello");
+/// ```
+#[cfg(feature = "std")]
+impl From<&Path> for Arc<Path> {
+    fn from(path: &Path) -> Self {
+        Self::from_os_str(Arc::from(path.as_os_str()))
+    }
+}
+
+/// Moves the path's bytes into a new `Arc<Path>` and frees the `PathBuf`'s buffer.
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// use std::path::{Path, PathBuf};
+/// let arc: Arc<Path> = Arc::from(PathBuf::from("/tmp/hello"));
+/// assert_eq!(&*arc, Path::new("/tmp/hello"));
+/// ```
+#[cfg(feature = "std")]
+impl From<PathBuf> for Arc<Path> {
+    fn from(path: PathBuf) -> Self {
+        Self::from_os_str(Arc::from(path.into_os_string()))
+    }
+}
+
+/// Erases the type of the data, keeping the same allocation. See [`Arc::downcast`] to get it back.
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// use std::any::Any;
+/// let arc = Arc::new(42);
+/// let any: Arc<dyn Any + Send + Sync> = Arc::from(arc.clone());
+/// assert_eq!(Arc::strong_count(&arc), 2);
+/// assert!(any.is::<i32>());
+/// ```
+impl<T: Any + Send + Sync> From<Arc<T>> for Arc<dyn Any + Send + Sync> {
+    fn from(arc: Arc<T>) -> Self {
+        // Our strong reference is handed over to the new `Arc`.
+        let arc = ManuallyDrop::new(arc);
+        // `NonNull` supports unsizing coercions (even on stable), which attaches `T`'s vtable to the pointer.
+        let data: NonNull<ArcData<dyn Any + Send + Sync>> = arc.data;
+        Self::from_inner(data)
+    }
+}
+
+/// Pins an `Arc<T>` whose data doesn't care about being moved anyway.
+impl<T: Unpin> From<Arc<T>> for Pin<Arc<T>> {
+    fn from(arc: Arc<T>) -> Self {
+        Pin::new(arc)
+    }
+}
+
+impl<T: ?Sized, A: Allocator> AsRef<T> for Arc<T, A> {
+    fn as_ref(&self) -> &T {
+        self
+    }
+}
+
+/// Lets maps keyed by `Arc<T>` be looked up with a `&T`. This is consistent with the [`Hash`], [`Eq`] and [`Ord`]
+/// impls, which all delegate to `T`.
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// use std::collections::HashMap;
+/// let mut map = HashMap::new();
+/// map.insert(Arc::new(String::from("answer")), 42);
+/// assert_eq!(map.get(&String::from("answer")), Some(&42));
+///
+/// // `Arc<str>` keys can be looked up with a plain `&str`.
+/// let mut map = HashMap::new();
+/// map.insert(Arc::<str>::from("answer"), 42);
+/// assert_eq!(map.get("answer"), Some(&42));
+/// ```
+impl<T: ?Sized, A: Allocator> Borrow<T> for Arc<T, A> {
+    fn borrow(&self) -> &T {
+        self
+    }
+}
+
+/// Compares the data, not the pointers (see [`Arc::ptr_eq`] for that).
+///
+/// With the `nightly` feature, two `Arc`s pointing to the same allocation are equal without calling `T::eq` when
+/// `T: Eq`, like with std's `Arc`. That isn't done for any other `T`, since it would be wrong for values like
+/// `f64::NAN`.
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// assert!(Arc::new(42) == Arc::new(42));
+/// let nan = Arc::new(f64::NAN);
+/// assert!(nan != nan.clone());
+/// ```
+impl<T: ?Sized + PartialEq, A: Allocator> PartialEq for Arc<T, A> {
+    fn eq(&self, other: &Self) -> bool {
+        ArcEqIdent::eq(self, other)
+    }
+
+    // Forwarded rather than left as `!eq` so that `T`'s own `ne` is used, like with std's `Arc`.
+    #[allow(clippy::partialeq_ne_impl)]
+    fn ne(&self, other: &Self) -> bool {
+        ArcEqIdent::ne(self, other)
+    }
+}
+
+/// Backs the [`PartialEq`] impl, so that it can be specialized for `T: Eq`.
+trait ArcEqIdent<T: ?Sized + PartialEq, A: Allocator> {
+    fn eq(&self, other: &Self) -> bool;
+    fn ne(&self, other: &Self) -> bool;
+}
+
+impl<T: ?Sized + PartialEq, A: Allocator> ArcEqIdent<T, A> for Arc<T, A> {
+    #[cfg(feature = "nightly")]
+    default fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+
+    #[cfg(not(feature = "nightly"))]
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+
+    #[cfg(feature = "nightly")]
+    default fn ne(&self, other: &Self) -> bool {
+        **self != **other
+    }
+
+    #[cfg(not(feature = "nightly"))]
+    fn ne(&self, other: &Self) -> bool {
+        **self != **other
+    }
+}
+
+/// `T: Eq` promises that a value equals itself, so two `Arc`s sharing an allocation don't need comparing.
+#[cfg(feature = "nightly")]
+impl<T: ?Sized + MarkerEq, A: Allocator> ArcEqIdent<T, A> for Arc<T, A> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(self, other) || **self == **other
+    }
+
+    fn ne(&self, other: &Self) -> bool {
+        !Arc::ptr_eq(self, other) && **self != **other
+    }
+}
+
+/// `min_specialization` can't specialize on `Eq` itself, so std uses a marker trait like this one.
+#[cfg(feature = "nightly")]
+#[rustc_unsafe_specialization_marker]
+trait MarkerEq: PartialEq<Self> {}
+
+#[cfg(feature = "nightly")]
+impl<T: ?Sized + Eq> MarkerEq for T {}
+
+impl<T: ?Sized + Eq, A: Allocator> Eq for Arc<T, A> {}
+
+/// Compares the data, not the pointers.
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// assert!(Arc::new(1) < Arc::new(2));
+/// ```
+impl<T: ?Sized + PartialOrd, A: Allocator> PartialOrd for Arc<T, A> {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        (**self).partial_cmp(&**other)
+    }
+
+    fn lt(&self, other: &Self) -> bool {
+        **self < **other
+    }
+
+    fn le(&self, other: &Self) -> bool {
+        **self <= **other
+    }
+
+    fn gt(&self, other: &Self) -> bool {
+        **self > **other
+    }
+
+    fn ge(&self, other: &Self) -> bool {
+        **self >= **other
+    }
+}
+
+impl<T: ?Sized + Ord, A: Allocator> Ord for Arc<T, A> {
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        (**self).cmp(&**other)
+    }
+}
+
+/// Hashes the data, not the pointer, consistently with the [`PartialEq`] impl.
+impl<T: ?Sized + Hash, A: Allocator> Hash for Arc<T, A> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
+/// Formats the data, exactly like `T` would (the reference counts aren't printed), flags like `{:#?}` included.
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// let arc = Arc::new(vec![4, 2]);
+/// assert_eq!(format!("{arc:?}"), "[4, 2]");
+/// assert_eq!(format!("{arc:#?}"), format!("{:#?}", vec![4, 2]));
+///
+/// let name: Arc<str> = Arc::from("hello");
+/// assert_eq!(format!("{name:?}"), "\"hello\"");
+/// ```
+impl<T: ?Sized + fmt::Debug, A: Allocator> fmt::Debug for Arc<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// Formats the data, exactly like `T` would.
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// let arc = Arc::new("Hello");
+/// assert_eq!(format!("{arc}"), "Hello");
+///
+/// let name: Arc<str> = Arc::from("world");
+/// assert_eq!(format!("{arc}, {name}!"), "Hello, world!");
+/// assert_eq!(format!("{name:>7}"), "  world");
+/// ```
+impl<T: ?Sized + fmt::Display, A: Allocator> fmt::Display for Arc<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+/// Formats the address of the data (the same one [`Arc::as_ptr`] returns).
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// let arc = Arc::new(42);
+/// assert_eq!(format!("{arc:p}"), format!("{:p}", Arc::as_ptr(&arc)));
+/// ```
+impl<T: ?Sized, A: Allocator> fmt::Pointer for Arc<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Pointer::fmt(&Self::as_ptr(self), f)
+    }
+}
+
+/// Forwards to the inner error, including its [`Error::source`] chain.
+impl<T: ?Sized + Error, A: Allocator> Error for Arc<T, A> {
+    #[allow(deprecated)]
+    fn description(&self) -> &str {
+        Error::description(&**self)
+    }
+
+    #[allow(deprecated)]
+    fn cause(&self) -> Option<&dyn Error> {
+        Error::cause(&**self)
+    }
+
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Error::source(&**self)
+    }
+}
+
+/// Serializes the data, exactly like `T` would (the reference counts aren't serialized, and data shared by
+/// several `Arc<T>`s is serialized once for each of them).
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// assert_eq!(serde_json::to_string(&Arc::new(42)).unwrap(), "42");
+/// ```
+#[cfg(feature = "serde")]
+impl<T: ?Sized + serde::Serialize, A: Allocator> serde::Serialize for Arc<T, A> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (**self).serialize(serializer)
+    }
+}
+
+/// Deserializes a `T` and puts it in a new `Arc<T>`.
+/// # Examples
+/// ```
+/// use arc::Arc;
+/// let arc: Arc<Vec<u32>> = serde_json::from_str("[4, 2]").unwrap();
+/// assert_eq!(*arc, [4, 2]);
+/// ```
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for Arc<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Self::new)
+    }
+}
+
+// The allocator is shared just like the data (and used by whichever thread frees the allocation).
+unsafe impl<T: ?Sized + Send + Sync, A: Allocator + Send + Sync> Send for Arc<T, A> {}
+unsafe impl<T: ?Sized + Send + Sync, A: Allocator + Send + Sync> Sync for Arc<T, A> {}
+
+// The handle itself can always be moved around freely, even when the data can't (see `Arc::pin`).
+impl<T: ?Sized, A: Allocator> Unpin for Arc<T, A> {}
+
+// The `UnsafeCell` around the data would otherwise opt us out of these, but an `Arc<T>` only hands out shared
+// references (or a mutable one when unique), so it's exactly as unwind safe as a `&T`.
+impl<T: ?Sized + RefUnwindSafe, A: Allocator + RefUnwindSafe> UnwindSafe for Arc<T, A> {}
+impl<T: ?Sized + RefUnwindSafe, A: Allocator + RefUnwindSafe> RefUnwindSafe for Arc<T, A> {}
 
-unsafe impl<T: Send + Sync> Send for Arc<T> {}
-unsafe impl<T: Send + Sync> Sync for Arc<T> {}
+// Like for std's `Arc`, these let an `Arc<T>` turn into an `Arc<dyn Trait>` (or `Arc<[T; N]>` into `Arc<[T]>`)
+// implicitly, and `self: Arc<Self>` methods be object safe. Both are still unstable, and unlike with std's `Arc`,
+// declaring such a method also needs `#![feature(arbitrary_self_types)]`.
+#[cfg(feature = "nightly")]
+impl<T: ?Sized + Unsize<U>, U: ?Sized, A: Allocator> CoerceUnsized<Arc<U, A>> for Arc<T, A> {}
+#[cfg(feature = "nightly")]
+impl<T: ?Sized + Unsize<U>, U: ?Sized> DispatchFromDyn<Arc<U>> for Arc<T> {}
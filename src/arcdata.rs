@@ -1,15 +1,507 @@
-pub(crate) use std::sync::atomic::{fence, AtomicUsize, Ordering};
+#[cfg(test)]
+use core::cell::Cell;
+pub(crate) use core::cell::UnsafeCell;
+pub(crate) use core::mem::ManuallyDrop;
+pub(crate) use core::ptr::NonNull;
+#[cfg(feature = "std")]
+pub(crate) use core::sync::atomic::AtomicPtr;
+pub(crate) use core::sync::atomic::{fence, AtomicUsize, Ordering};
 
-pub(crate) struct ArcData<T> {
-    pub(crate) refs: AtomicUsize,
-    pub(crate) data: T,
+use crate::allocator::{Allocator, Global};
+use crate::error::AllocError;
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::alloc::Layout;
+use core::mem::MaybeUninit;
+use core::ops::Deref;
+#[cfg(feature = "std")]
+use std::thread::Thread;
+
+/// Set in `ArcData::refs` (on top of the count itself) while a thread waits in `Arc::into_inner_blocking`. The
+/// `Arc<T>` whose decrement turns `WAITING | 2` into `WAITING | 1` has to call `ArcData::wake_waiter`, and the
+/// waiter doesn't return before the flag is cleared, so that call can't outlive the allocation. Since counts
+/// above `MAX_REFS` abort, they can never reach this bit.
+pub(crate) const WAITING: usize = 1 << (usize::BITS - 1);
+
+/// The most strong or weak references an allocation can have. Incrementing a count that's already above this
+/// aborts (see `abort`), and `Arc::try_clone` refuses to go past it. It's far enough from `usize::MAX` that even
+/// lots of threads incrementing at the same time can't wrap the count around before one of them aborts.
+pub(crate) const MAX_REFS: usize = usize::MAX / 3;
+
+#[cfg(test)]
+std::thread_local! {
+    /// Makes every allocation on this thread fail while set, so tests can exercise the `try_*` error paths.
+    pub(crate) static FAIL_ALLOCATIONS: Cell<bool> = const { Cell::new(false) };
+}
+
+/// A finalizer (see `Arc::new_with_finalizer`), boxed once more so that it only takes a thin pointer in every
+/// `ArcData<T>`. It's given the address of the data instead of a `&mut T`, so that it doesn't depend on `T` and
+/// stays correct when the `ArcData<T>` is reinterpreted as some other type (like an `ArcData<dyn Any>`).
+pub(crate) type Finalizer = Box<dyn FnOnce(*mut u8) + Send>;
+
+/// One of the reference counts in an `ArcData<T>`.
+///
+/// With the `cache_padded` feature, each count gets a cache line to itself, so threads cloning and dropping
+/// `Arc<T>`s don't keep taking the line away from threads reading the data or working with `Weak<T>`s (and the
+/// other way around). That makes every allocation at least 64 bytes bigger (up to 127, padding included), which
+/// only pays off for `Arc<T>`s that are cloned and dropped a lot from many threads at once.
+#[cfg_attr(feature = "cache_padded", repr(align(64)))]
+pub(crate) struct Counter(AtomicUsize);
+
+impl Counter {
+    pub(crate) const fn new(n: usize) -> Self {
+        Self(AtomicUsize::new(n))
+    }
+}
+
+impl Deref for Counter {
+    type Target = AtomicUsize;
+    fn deref(&self) -> &AtomicUsize {
+        &self.0
+    }
+}
+
+// `repr(C)` guarantees an `ArcData<MaybeUninit<T>>` has the same layout as an `ArcData<T>`, so an allocation
+// can be created for one and then used as the other. It also fixes where the data starts for unsized `T`s (it's
+// always the last field), which lets `ArcData<[T]>` allocations be laid out by hand.
+#[repr(C)]
+pub(crate) struct ArcData<T: ?Sized, A: Allocator = Global> {
+    /// Number of `Arc<T>`s.
+    pub(crate) refs: Counter,
+    /// Number of `Weak<T>`s, plus one if there are any `Arc<T>`s.
+    pub(crate) weak: Counter,
+    /// Run right before the data is dropped in place, if set.
+    pub(crate) finalizer: UnsafeCell<Option<Box<Finalizer>>>,
+    /// The thread waiting in `Arc::into_inner_blocking` for the strong count to drop to 1, or null. Points into
+    /// that thread's stack, and may only be dereferenced while `WAITING` is set in `refs`.
+    #[cfg(feature = "std")]
+    pub(crate) waiter: AtomicPtr<Thread>,
+    /// Where the allocation came from, and goes back to in the end (see `deallocate`). Zero-sized for `Global`.
+    pub(crate) alloc: ManuallyDrop<A>,
+    /// Dropped (but not deallocated) as soon as there are no `Arc<T>`s left, even if some `Weak<T>`s remain.
+    pub(crate) data: UnsafeCell<ManuallyDrop<T>>,
 }
 
 impl<T> ArcData<T> {
     pub(crate) fn new(data: T) -> Self {
+        Self::new_in(data, Global)
+    }
+
+    /// Moves `data` into a new heap allocation, or hands it back if allocating fails.
+    ///
+    /// The allocation uses the global allocator with `ArcData<T>`'s layout, just like a `Box<ArcData<T>>` would.
+    pub(crate) fn try_allocate(data: T) -> Result<NonNull<Self>, T> {
+        let Some(ptr) = NonNull::new(unsafe { alloc(Layout::new::<Self>(), false) }.cast::<Self>())
+        else {
+            return Err(data);
+        };
+        unsafe { ptr.write(Self::new(data)) };
+        Ok(ptr)
+    }
+
+    /// The inverse of `data_ptr`.
+    ///
+    /// # Safety
+    /// `ptr` must have been returned by `data_ptr` (with the same `T`).
+    pub(crate) unsafe fn from_data_ptr(ptr: *const T) -> NonNull<Self> {
+        // `ptr` points to the `data` field, so step back to the start of the `ArcData<T>` (which is `repr(C)`,
+        // so the offset is the same for every allocation of this `T`).
+        let data = unsafe { ptr.byte_sub(core::mem::offset_of!(Self, data)) };
+        unsafe { NonNull::new_unchecked(data.cast::<Self>().cast_mut()) }
+    }
+}
+
+impl<T: ?Sized> ArcData<T> {
+    /// The inverse of `data_ptr`, like `from_data_ptr`, but for unsized `T`s too.
+    ///
+    /// # Safety
+    /// `ptr` must have been returned by `data_ptr` (with the same `T`), and the data must not have been dropped.
+    pub(crate) unsafe fn from_unsized_data_ptr(ptr: *const T) -> NonNull<Self> {
+        // The data starts at the first offset after the header that's aligned for it, which for an unsized `T`
+        // depends on the data itself (the alignment of a `dyn Trait` is in its vtable), so it has to be asked.
+        // The header ends where an `ArcData<[u8; 0]>`'s data starts, since that needs no alignment at all.
+        let align = core::mem::align_of_val(unsafe { &*ptr });
+        let offset = core::mem::offset_of!(ArcData<[u8; 0]>, data).next_multiple_of(align);
+        let data = unsafe { ptr.byte_sub(offset) };
+        // The cast keeps the metadata, which is the same for an `ArcData<T>` as for its `T`.
+        unsafe { NonNull::new_unchecked(data as *mut Self) }
+    }
+}
+
+impl<T: ?Sized, A: Allocator> ArcData<T, A> {
+    /// Returns a pointer to the data in the `ArcData<T>` behind `ptr`.
+    ///
+    /// This never creates a reference (neither to the `ArcData<T>` nor to the data), so it's fine to call even
+    /// after the data was dropped. It also keeps the provenance of the whole allocation, so the result can be
+    /// stepped back to the start of the `ArcData<T>` (see `from_data_ptr`).
+    pub(crate) fn data_ptr(ptr: NonNull<Self>) -> *const T {
+        // `UnsafeCell` and `ManuallyDrop` are both `repr(transparent)`, so a pointer to the field is a pointer to `T`.
+        unsafe { &raw const (*ptr.as_ptr()).data as *const T }
+    }
+
+    /// Runs the finalizer (if there is one), then `unlink` (see `Arc::drop_iterative`), then drops the data in
+    /// place.
+    ///
+    /// The data is dropped even if the finalizer or `unlink` panics, so `T`'s destructor always runs exactly once.
+    ///
+    /// # Safety
+    /// May only be called once, by whoever dropped the strong count to 0. The data must not be used afterwards.
+    pub(crate) unsafe fn drop_data(&self, unlink: impl FnOnce(&mut T)) {
+        // Drops the data when it goes out of scope, which includes unwinding out of the finalizer.
+        struct DropData<T: ?Sized>(*mut ManuallyDrop<T>);
+
+        impl<T: ?Sized> Drop for DropData<T> {
+            fn drop(&mut self) {
+                unsafe { ManuallyDrop::drop(&mut *self.0) }
+            }
+        }
+
+        let data = DropData(self.data.get());
+        if let Some(finalizer) = unsafe { (*self.finalizer.get()).take() } {
+            finalizer(data.0.cast());
+        }
+        // SAFETY: The strong count is 0, so nothing else can get at the data anymore.
+        unlink(unsafe { &mut *data.0 });
+    }
+
+    /// Wakes up the thread waiting in `Arc::into_inner_blocking` and clears `WAITING`. This takes a pointer
+    /// rather than `&self`, since the allocation may be freed before it returns.
+    ///
+    /// # Safety
+    /// `ptr` must point to an allocation whose strong count the caller just decremented from `WAITING | 2`.
+    #[cfg(feature = "std")]
+    pub(crate) unsafe fn wake_waiter(ptr: NonNull<Self>) {
+        // Pairs with the release `fetch_or` that set `WAITING`, after the waiter was stored.
+        fence(Ordering::Acquire);
+        // SAFETY: `WAITING` is still set, so the waiter hasn't returned yet, and its `Thread` is still there.
+        let waiter = unsafe { (*ptr.as_ref().waiter.load(Ordering::Relaxed)).clone() };
+        // This is the last time we touch the allocation: once the flag is gone, the waiter may free it. Release
+        // makes sure it really is the last time (the waiter's acquire load sees everything before it).
+        unsafe { ptr.as_ref() }
+            .refs
+            .fetch_and(!WAITING, Ordering::Release);
+        waiter.unpark();
+    }
+
+    /// Frees the allocation through the allocator it came from, after dropping what's left in it (the data has to
+    /// be dropped or moved out beforehand, but the finalizer may still be there).
+    ///
+    /// # Safety
+    /// May only be called once, by whoever dropped the weak count to 0. The allocation must not be used afterwards.
+    pub(crate) unsafe fn deallocate(ptr: NonNull<Self>) {
+        unsafe {
+            // The same layout the allocation was made with, whether that was for a sized `T` or by hand for an
+            // `ArcData<[T]>` (see `slice_layout`).
+            let layout = Layout::for_value(ptr.as_ref());
+            let alloc = ManuallyDrop::take(&mut (*ptr.as_ptr()).alloc);
+            core::ptr::drop_in_place(ptr.as_ptr());
+            alloc.deallocate(ptr.cast(), layout);
+        }
+    }
+}
+
+impl<T, A: Allocator> ArcData<T, A> {
+    pub(crate) fn new_in(data: T, alloc: A) -> Self {
+        Self {
+            refs: Counter::new(1),
+            // All `Arc<T>`s collectively hold a single weak reference, which the last `Arc<T>` to be dropped gives back.
+            weak: Counter::new(1),
+            finalizer: UnsafeCell::new(None),
+            #[cfg(feature = "std")]
+            waiter: AtomicPtr::new(core::ptr::null_mut()),
+            alloc: ManuallyDrop::new(alloc),
+            data: UnsafeCell::new(ManuallyDrop::new(data)),
+        }
+    }
+
+    /// Like `try_allocate`, but gets the memory from `alloc`, which then lives in the header until the allocation
+    /// is given back to it. Hands both back if allocating fails.
+    #[cfg(feature = "allocator_api")]
+    pub(crate) fn try_allocate_in(data: T, alloc: A) -> Result<NonNull<Self>, (T, A)> {
+        let Ok(ptr) = alloc.allocate(Layout::new::<Self>()) else {
+            return Err((data, alloc));
+        };
+        let ptr = ptr.cast::<Self>();
+        unsafe { ptr.write(Self::new_in(data, alloc)) };
+        Ok(ptr)
+    }
+}
+
+impl<T> ArcData<MaybeUninit<T>> {
+    /// Allocates an `ArcData<MaybeUninit<T>>` with both counts set to 1, or returns an [`AllocError`] if allocating
+    /// fails. The data is left uninitialized, or filled with zero bytes if `zeroed` is set.
+    ///
+    /// Nothing is written to the data (not even a `MaybeUninit<T>` moved in from the stack, which could be large),
+    /// only the rest of the header (see `init_header`). The zeroes come straight from the allocator, which can often
+    /// hand out memory it knows to be zeroed already.
+    pub(crate) fn try_allocate_uninit(zeroed: bool) -> Result<NonNull<Self>, AllocError> {
+        let ptr = unsafe { alloc(Layout::new::<Self>(), zeroed) };
+        let ptr = NonNull::new(ptr.cast::<Self>()).ok_or(AllocError)?;
+        unsafe { Self::init_header(ptr) };
+        Ok(ptr)
+    }
+}
+
+impl<T: ?Sized> ArcData<T> {
+    /// Sets up the header of a fresh allocation from the global allocator like `new` does, without touching
+    /// the data.
+    ///
+    /// # Safety
+    /// `ptr` must point to memory allocated for an `ArcData<T>`, which nothing else is using yet.
+    unsafe fn init_header(ptr: NonNull<Self>) {
+        unsafe {
+            (&raw mut (*ptr.as_ptr()).refs).write(Counter::new(1));
+            (&raw mut (*ptr.as_ptr()).weak).write(Counter::new(1));
+            (&raw mut (*ptr.as_ptr()).finalizer).write(UnsafeCell::new(None));
+            #[cfg(feature = "std")]
+            (&raw mut (*ptr.as_ptr()).waiter).write(AtomicPtr::new(core::ptr::null_mut()));
+            (&raw mut (*ptr.as_ptr()).alloc).write(ManuallyDrop::new(Global));
+        }
+    }
+}
+
+impl<T: ?Sized> ArcData<T> {
+    /// Moves the value out of `boxed` into a new allocation (with an initialized header, see `init_header`), then
+    /// frees the `Box` without dropping the value, which belongs to the new allocation now.
+    ///
+    /// Works for unsized values too: the layout comes from the value itself, and `with_metadata` turns the
+    /// address of the new allocation into a pointer to an `ArcData<T>`, by giving it the metadata (the length, or
+    /// the vtable of a trait object) of the `Box`'s pointer it's passed along with.
+    ///
+    /// # Safety
+    /// `with_metadata` must return its first argument, with the metadata of the second.
+    pub(crate) unsafe fn from_box(
+        boxed: Box<T>,
+        with_metadata: impl FnOnce(*mut u8, *mut T) -> *mut Self,
+    ) -> NonNull<Self> {
+        let value = Layout::for_value(&*boxed);
+        // The same layout `Layout::for_value` gives for an `ArcData<T>` holding this value (which is what
+        // `deallocate` frees it with): the header, padding up to the value's alignment, then the value, then
+        // padding up to the alignment of the whole thing. The header ends where an `ArcData<[u8; 0]>`'s data
+        // starts, since that needs no alignment at all.
+        let header = Layout::from_size_align(
+            core::mem::offset_of!(ArcData<[u8; 0]>, data),
+            core::mem::align_of::<ArcData<[u8; 0]>>(),
+        )
+        .unwrap();
+        let (layout, _) = header.extend(value).expect("capacity overflow");
+        let layout = layout.pad_to_align();
+        let Some(ptr) = NonNull::new(unsafe { alloc(layout, false) }) else {
+            alloc::alloc::handle_alloc_error(layout);
+        };
+        let boxed = Box::into_raw(boxed);
+        let ptr = unsafe { NonNull::new_unchecked(with_metadata(ptr.as_ptr(), boxed)) };
+        unsafe {
+            Self::init_header(ptr);
+            let data = Self::data_ptr(ptr).cast_mut().cast::<u8>();
+            core::ptr::copy_nonoverlapping(boxed.cast::<u8>(), data, value.size());
+            // A `ManuallyDrop<T>` has the same layout as a `T`, and dropping it in a `Box` only frees the memory.
+            drop(Box::from_raw(boxed as *mut ManuallyDrop<T>));
+        }
+        ptr
+    }
+}
+
+/// The allocation all empty `Arc<[T]>`s, `Arc<str>`s and `Arc<CStr>`s made by `Default` share (see
+/// `ArcData::static_empty`), instead of each allocating a header of its own.
+///
+/// Its strong count starts at 1, for a reference that only the static itself holds and never gives back. So the
+/// count never drops to 0, the data is never dropped, and nothing ever tries to free it. The data is a single nul
+/// byte, which empty `Arc<[T]>`s just ignore, but an empty `Arc<CStr>` needs.
+#[repr(C, align(16))]
+struct StaticEmpty(ArcData<[u8; 1]>);
+
+// Everything in the header that's ever written to is atomic, and the finalizer is never set.
+unsafe impl Sync for StaticEmpty {}
+
+static STATIC_EMPTY: StaticEmpty = StaticEmpty(ArcData {
+    refs: Counter::new(1),
+    weak: Counter::new(1),
+    finalizer: UnsafeCell::new(None),
+    #[cfg(feature = "std")]
+    waiter: AtomicPtr::new(core::ptr::null_mut()),
+    alloc: ManuallyDrop::new(Global),
+    data: UnsafeCell::new(ManuallyDrop::new([0])),
+});
+
+impl<T> ArcData<[T]> {
+    /// Returns `STATIC_EMPTY` as an empty `ArcData<[T]>`, or `None` if `T` needs more alignment than it has.
+    ///
+    /// The strong reference the static holds isn't handed out: the caller has to increment the strong count for
+    /// any `Arc<[T]>` it creates from the pointer.
+    pub(crate) fn static_empty() -> Option<NonNull<Self>> {
+        // The data starts at the first offset after the header that's aligned for `T`, and with zero elements, it
+        // ends right there too. Aligning the whole static to at least `T`'s alignment keeps that within it.
+        (core::mem::align_of::<T>() <= core::mem::align_of::<StaticEmpty>())
+            .then(|| static_slice(0))
+    }
+}
+
+impl ArcData<[u8]> {
+    /// Like `static_empty`, but including the nul byte, for an empty `Arc<CStr>`.
+    pub(crate) fn static_nul() -> NonNull<Self> {
+        static_slice(1)
+    }
+}
+
+/// Returns `STATIC_EMPTY` as an `ArcData<[T]>` of length `len`.
+fn static_slice<T>(len: usize) -> NonNull<ArcData<[T]>> {
+    // Derived from a reference to the whole static (not just the `ArcData` in it), so that the pointer may access
+    // all of its bytes, wherever the data ends up for `T`. Everything it's used to write to is in an `UnsafeCell`.
+    let ptr = NonNull::from(&STATIC_EMPTY).cast::<T>();
+    // The metadata of a pointer to an `ArcData<[T]>` is the length of its slice, just like for a `[T]`.
+    let ptr = NonNull::slice_from_raw_parts(ptr, len);
+    unsafe { NonNull::new_unchecked(ptr.as_ptr() as *mut ArcData<[T]>) }
+}
+
+impl<T> ArcData<[T]> {
+    /// Allocates an `ArcData<[T]>` with room for `len` elements and an initialized header (see `init_header`), but
+    /// leaves the elements uninitialized (or all zero bytes, if `zeroed` is set): the caller has to write all of
+    /// them before handing the allocation to an `Arc<[T]>`.
+    ///
+    /// Like `try_allocate`, this uses the global allocator with the same layout a `Box<ArcData<[T]>>` of that
+    /// length would have, which is what `deallocate` frees it with.
+    pub(crate) fn allocate_slice(len: usize, zeroed: bool) -> NonNull<Self> {
+        let layout = Self::slice_layout(len);
+        let Some(ptr) = NonNull::new(unsafe { alloc(layout, zeroed) }) else {
+            alloc::alloc::handle_alloc_error(layout);
+        };
+        // The metadata of a pointer to an `ArcData<[T]>` is the length of its slice, just like for a `[T]`.
+        let ptr = NonNull::slice_from_raw_parts(ptr.cast::<T>(), len);
+        let ptr = unsafe { NonNull::new_unchecked(ptr.as_ptr() as *mut Self) };
+        unsafe { Self::init_header(ptr) };
+        ptr
+    }
+
+    /// Returns the layout of an `ArcData<[T]>` holding `len` elements: the header, padding up to `T`'s alignment,
+    /// then the elements, then padding up to the alignment of the whole thing.
+    ///
+    /// # Panics
+    /// Panics if the allocation would be larger than `isize::MAX` bytes.
+    fn slice_layout(len: usize) -> Layout {
+        // An `ArcData<[T; 0]>` is just the header (everything but the data), aligned for both it and `T`. Its size
+        // can include padding past where the elements start (when the header is more aligned than `T`, as with
+        // `cache_padded`), so the elements are placed at the offset of `data` instead.
+        let header = Layout::new::<ArcData<[T; 0]>>();
+        let offset = core::mem::offset_of!(ArcData<[T; 0]>, data);
+        Layout::array::<T>(len)
+            .ok()
+            .and_then(|elements| offset.checked_add(elements.size()))
+            .and_then(|size| Layout::from_size_align(size, header.align()).ok())
+            .expect("capacity overflow")
+            .pad_to_align()
+    }
+}
+
+/// A fresh `ArcData<[T]>` allocation whose elements are written one at a time.
+///
+/// If it's dropped before all of them were written (e.g. because producing one panicked), the elements written so
+/// far are dropped and the allocation is freed, so nothing leaks and nothing uninitialized is ever dropped.
+pub(crate) struct SliceBuilder<T> {
+    data: NonNull<ArcData<[T]>>,
+    capacity: usize,
+    len: usize,
+}
+
+impl<T> SliceBuilder<T> {
+    pub(crate) fn new(capacity: usize) -> Self {
         Self {
-            refs: AtomicUsize::new(1),
-            data,
+            data: ArcData::allocate_slice(capacity, false),
+            capacity,
+            len: 0,
+        }
+    }
+
+    pub(crate) fn is_full(&self) -> bool {
+        self.len == self.capacity
+    }
+
+    /// Writes the next element.
+    ///
+    /// # Panics
+    /// Panics if all elements were already written.
+    pub(crate) fn push(&mut self, value: T) {
+        assert!(!self.is_full(), "SliceBuilder is already full");
+        unsafe { self.elements().add(self.len).write(value) };
+        self.len += 1;
+    }
+
+    /// Returns the allocation, with all of its elements written.
+    ///
+    /// # Panics
+    /// Panics if some elements are still missing.
+    pub(crate) fn finish(self) -> NonNull<ArcData<[T]>> {
+        assert!(self.is_full(), "SliceBuilder is missing elements");
+        ManuallyDrop::new(self).data
+    }
+
+    /// Moves the elements written so far into a `Vec<T>` instead, and frees the allocation.
+    pub(crate) fn into_vec(mut self) -> Vec<T> {
+        let mut vec = Vec::with_capacity(self.len);
+        unsafe {
+            core::ptr::copy_nonoverlapping(self.elements(), vec.as_mut_ptr(), self.len);
+            vec.set_len(self.len);
         }
+        // The elements belong to `vec` now, so dropping `self` must only free the allocation.
+        self.len = 0;
+        vec
+    }
+
+    fn elements(&self) -> *mut T {
+        unsafe { (&raw mut (*self.data.as_ptr()).data).cast::<T>() }
+    }
+}
+
+impl<T> Drop for SliceBuilder<T> {
+    fn drop(&mut self) {
+        unsafe {
+            core::ptr::drop_in_place(core::ptr::slice_from_raw_parts_mut(
+                self.elements(),
+                self.len,
+            ));
+            alloc::alloc::dealloc(
+                self.data.as_ptr().cast(),
+                ArcData::<[T]>::slice_layout(self.capacity),
+            );
+        }
+    }
+}
+
+/// Allocates memory for `layout` with the global allocator (filled with zeroes if `zeroed` is set), returning a
+/// null pointer on failure.
+///
+/// # Safety
+/// `layout` must have a non-zero size (which is always the case for an `ArcData<T>`, since it holds the counts).
+unsafe fn alloc(layout: Layout, zeroed: bool) -> *mut u8 {
+    #[cfg(test)]
+    if FAIL_ALLOCATIONS.get() {
+        return core::ptr::null_mut();
+    }
+    if zeroed {
+        unsafe { alloc::alloc::alloc_zeroed(layout) }
+    } else {
+        unsafe { alloc::alloc::alloc(layout) }
+    }
+}
+
+/// Aborts the process, for when a reference count is about to overflow. Without `std`, there's no way to do that
+/// directly, but panicking while already panicking aborts too (and with `panic = "abort"`, the first panic does).
+pub(crate) fn abort() -> ! {
+    #[cfg(feature = "std")]
+    std::process::abort();
+
+    #[cfg(not(feature = "std"))]
+    {
+        struct Abort;
+
+        impl Drop for Abort {
+            fn drop(&mut self) {
+                panic!("reference count overflow");
+            }
+        }
+
+        let _abort = Abort;
+        panic!("reference count overflow");
     }
 }
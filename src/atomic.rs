@@ -0,0 +1,295 @@
+use crate::arc::Arc;
+use crate::arcdata::{abort, ArcData, MAX_REFS, WAITING};
+
+use core::fmt;
+use core::mem::ManuallyDrop;
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+/// An `Arc<T>` that can be loaded and replaced atomically, for sharing a value that's swapped out every now and
+/// then (like a configuration) between threads that read it all the time.
+///
+/// Loading is the hard part: a reader has to increment the strong count of the `ArcData<T>` it loaded the pointer
+/// to, but by then, a writer may have swapped that `Arc<T>` out and dropped it, freeing the allocation. So readers
+/// announce themselves in the low bits of the pointer (which are always zero, given `ArcData<T>`'s alignment)
+/// before they increment the strong count, and take the announcement back after. A writer swaps the pointer and
+/// the announcements in it out at once, so it knows how many readers may still be about to increment the old
+/// strong count. It doesn't wait for them: it leaves each of them a strong reference instead, which the reader
+/// drops again once it notices its announcement is gone. Those come out of a few strong references reserved
+/// whenever an `Arc<T>` is stored (before anything can be loading it), which is why [`Arc::strong_count`] counts
+/// the `AtomicArc<T>` more than once.
+///
+/// So [`swap`](Self::swap), [`store`](Self::store) and a successful [`compare_exchange`](Self::compare_exchange)
+/// never wait for anything, and neither does [`load`](Self::load), short of retrying when another thread changed
+/// the pointer at the same time. The one exception is a load finding the low bits full: that's at least 7 loads in
+/// progress at once with 64-bit pointers (3 with 32-bit ones), each in the few instructions between announcing
+/// itself and taking it back. It then waits for one of them to finish (or for a writer to swap the pointer),
+/// yielding to other threads with `std`.
+///
+/// # Examples
+/// ```
+/// use arc::{Arc, AtomicArc};
+/// let config = AtomicArc::new(Arc::new(String::from("v1")));
+/// std::thread::scope(|s| {
+///     s.spawn(|| {
+///         let current = config.load();
+///         assert!(*current == "v1" || *current == "v2");
+///     });
+///     config.store(Arc::new(String::from("v2")));
+/// });
+/// assert_eq!(*config.load(), "v2");
+/// ```
+pub struct AtomicArc<T> {
+    // The `ArcData<T>` of the `Arc<T>` stored, whose strong reference (and `MAX_LOADS` more) this holds. Its low
+    // bits count the loads in progress.
+    pub(crate) ptr: AtomicPtr<ArcData<T>>,
+}
+
+// An `AtomicArc<T>` gives out clones of the `Arc<T>` it holds to any thread, so it's only `Send` or `Sync` when
+// `Arc<T>` is both.
+unsafe impl<T: Send + Sync> Send for AtomicArc<T> {}
+unsafe impl<T: Send + Sync> Sync for AtomicArc<T> {}
+
+impl<T> AtomicArc<T> {
+    /// How many loads can be in progress at once: as many as fit in the low bits of a pointer to an
+    /// `ArcData<T>`. That's also how many strong references are reserved for them along with each `Arc<T>` stored.
+    const MAX_LOADS: usize = align_of::<ArcData<T>>() - 1;
+
+    /// Creates a new `AtomicArc<T>` holding `arc`.
+    /// # Examples
+    /// ```
+    /// use arc::{Arc, AtomicArc};
+    /// let atomic = AtomicArc::new(Arc::new(42));
+    /// assert_eq!(*atomic.load(), 42);
+    /// ```
+    pub fn new(arc: Arc<T>) -> Self {
+        Self {
+            ptr: AtomicPtr::new(Self::reserve(arc)),
+        }
+    }
+
+    /// Returns a clone of the `Arc<T>` currently held.
+    /// # Examples
+    /// ```
+    /// use arc::{Arc, AtomicArc};
+    /// let arc = Arc::new(42);
+    /// let atomic = AtomicArc::new(arc.clone());
+    /// let loaded = atomic.load();
+    /// assert!(Arc::ptr_eq(&loaded, &arc));
+    /// // The strong references reserved for loads are given back along with the `AtomicArc`.
+    /// drop((atomic, loaded));
+    /// assert_eq!(Arc::strong_count(&arc), 1);
+    /// ```
+    pub fn load(&self) -> Arc<T> {
+        let mut current = self.ptr.load(Ordering::Acquire);
+        let announced = loop {
+            if Self::loads(current) == Self::MAX_LOADS {
+                backoff();
+                current = self.ptr.load(Ordering::Acquire);
+                continue;
+            }
+            match self.ptr.compare_exchange_weak(
+                current,
+                current.map_addr(|addr| addr + 1),
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => break Self::untag(current),
+                Err(actual) => current = actual,
+            }
+        };
+        // Until our announcement is gone, the allocation stays around: either the `Arc<T>` is still held, or the
+        // writer that swapped it out left us a strong reference first.
+        let arc = unsafe { Self::clone_ptr(announced) };
+        let mut current = self.ptr.load(Ordering::Acquire);
+        loop {
+            if Self::untag(current) != announced || Self::loads(current) == 0 {
+                // A writer swapped our announcement out, and left us a strong reference for it.
+                drop(Arc::from_inner(unsafe {
+                    NonNull::new_unchecked(announced)
+                }));
+                return arc;
+            }
+            // If the same `Arc<T>` was stored again in the meantime, this may take back another load's
+            // announcement instead of ours. That load then drops the strong reference left for ours, which is
+            // just as good: they're for the same allocation.
+            match self.ptr.compare_exchange_weak(
+                current,
+                current.map_addr(|addr| addr - 1),
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return arc,
+                Err(actual) => current = actual,
+            }
+        }
+    }
+
+    /// Replaces the `Arc<T>` held with `arc`, dropping the old one.
+    /// # Examples
+    /// ```
+    /// use arc::{Arc, AtomicArc};
+    /// let atomic = AtomicArc::new(Arc::new(1));
+    /// atomic.store(Arc::new(2));
+    /// assert_eq!(*atomic.load(), 2);
+    /// ```
+    pub fn store(&self, arc: Arc<T>) {
+        drop(self.swap(arc));
+    }
+
+    /// Replaces the `Arc<T>` held with `arc`, returning the old one.
+    /// # Examples
+    /// ```
+    /// use arc::{Arc, AtomicArc};
+    /// let atomic = AtomicArc::new(Arc::new(1));
+    /// assert_eq!(*atomic.swap(Arc::new(2)), 1);
+    /// assert_eq!(*atomic.load(), 2);
+    /// ```
+    pub fn swap(&self, arc: Arc<T>) -> Arc<T> {
+        let new = Self::reserve(arc);
+        unsafe { Self::release(self.ptr.swap(new, Ordering::AcqRel)) }
+    }
+
+    /// Replaces the `Arc<T>` held with `new`, but only if it's still `current` (the same allocation, see
+    /// [`Arc::ptr_eq`]).
+    ///
+    /// Returns the old `Arc<T>` if it was replaced. Otherwise, returns a clone of the `Arc<T>` held instead of
+    /// `current` (as [`load`](Self::load) would), along with `new`, so it can be tried again.
+    /// # Examples
+    /// ```
+    /// use arc::{Arc, AtomicArc};
+    /// let atomic = AtomicArc::new(Arc::new(1));
+    /// let current = atomic.load();
+    /// assert!(atomic.compare_exchange(&current, Arc::new(2)).is_ok());
+    /// let (actual, new) = atomic.compare_exchange(&current, Arc::new(3)).unwrap_err();
+    /// assert_eq!((*actual, *new), (2, 3));
+    /// ```
+    pub fn compare_exchange(
+        &self,
+        current: &Arc<T>,
+        new: Arc<T>,
+    ) -> Result<Arc<T>, (Arc<T>, Arc<T>)> {
+        let new = Self::reserve(new);
+        let mut held = self.ptr.load(Ordering::Acquire);
+        loop {
+            let held_data = ArcData::data_ptr(unsafe { NonNull::new_unchecked(Self::untag(held)) });
+            if !core::ptr::eq(held_data, Arc::as_ptr(current)) {
+                let actual = self.load();
+                if !Arc::ptr_eq(&actual, current) {
+                    // Nothing can have loaded `new` yet, so all of its reserved strong references are given back.
+                    return Err((actual, unsafe { Self::release(new) }));
+                }
+                // Swapped back to `current` in the meantime.
+                held = self.ptr.load(Ordering::Acquire);
+                continue;
+            }
+            // Fails if anything changed in the meantime, loads coming and going included.
+            match self
+                .ptr
+                .compare_exchange_weak(held, new, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(old) => return Ok(unsafe { Self::release(old) }),
+                Err(actual) => held = actual,
+            }
+        }
+    }
+
+    /// Returns the `Arc<T>` held.
+    /// # Examples
+    /// ```
+    /// use arc::{Arc, AtomicArc};
+    /// let atomic = AtomicArc::new(Arc::new(42));
+    /// assert_eq!(*atomic.into_inner(), 42);
+    /// ```
+    pub fn into_inner(self) -> Arc<T> {
+        let mut this = ManuallyDrop::new(self);
+        // No loads can be in progress, so none are left strong references.
+        unsafe { Self::release(*this.ptr.get_mut()) }
+    }
+
+    // Private functions
+    /// Turns `arc` into a pointer for `ptr`, reserving `MAX_LOADS` more strong references for the loads that may
+    /// be in progress when it's swapped out again.
+    fn reserve(arc: Arc<T>) -> *mut ArcData<T> {
+        let ptr = Arc::into_inner_ptr(arc);
+        // Same overflow check as in `Arc::clone`.
+        if unsafe { ptr.as_ref() }
+            .refs
+            .fetch_add(Self::MAX_LOADS, Ordering::Relaxed)
+            & !WAITING
+            > MAX_REFS
+        {
+            abort();
+        }
+        ptr.as_ptr()
+    }
+
+    /// Takes back the `Arc<T>` that `ptr` (with the loads in progress in its low bits) was just swapped out from
+    /// `self.ptr`, giving back the strong references it reserved except for one per load in progress. Those loads
+    /// drop theirs once they notice.
+    ///
+    /// # Safety
+    /// `ptr` must have been swapped out, so nothing else can take it back too.
+    unsafe fn release(ptr: *mut ArcData<T>) -> Arc<T> {
+        let data = unsafe { NonNull::new_unchecked(Self::untag(ptr)) };
+        // The `Arc<T>` returned keeps one more, so this can't be the last. Release matches the decrement in
+        // `Arc<T>`'s `Drop`.
+        unsafe { data.as_ref() }
+            .refs
+            .fetch_sub(Self::MAX_LOADS - Self::loads(ptr), Ordering::Release);
+        Arc::from_inner(data)
+    }
+
+    /// Returns how many loads are in progress, according to the low bits of `ptr`.
+    fn loads(ptr: *mut ArcData<T>) -> usize {
+        ptr.addr() & Self::MAX_LOADS
+    }
+
+    /// Returns `ptr` without the loads in progress in its low bits.
+    fn untag(ptr: *mut ArcData<T>) -> *mut ArcData<T> {
+        ptr.map_addr(|addr| addr & !Self::MAX_LOADS)
+    }
+
+    /// Returns a new strong reference to the allocation an `Arc<T>` handed over to `ptr`.
+    ///
+    /// # Safety
+    /// The allocation must be kept around until this returns, by some other strong reference.
+    unsafe fn clone_ptr(ptr: *mut ArcData<T>) -> Arc<T> {
+        let arc = ManuallyDrop::new(Arc::from_inner(unsafe { NonNull::new_unchecked(ptr) }));
+        Arc::clone(&arc)
+    }
+}
+
+/// Waits a little before a load checks again whether it can announce itself. Whoever it's waiting for may not even
+/// be running, so with `std`, it lets other threads run in the meantime.
+fn backoff() {
+    #[cfg(feature = "std")]
+    std::thread::yield_now();
+    #[cfg(not(feature = "std"))]
+    core::hint::spin_loop();
+}
+
+impl<T> Drop for AtomicArc<T> {
+    fn drop(&mut self) {
+        // Like `into_inner`, no loads can be in progress.
+        drop(unsafe { Self::release(*self.ptr.get_mut()) });
+    }
+}
+
+impl<T> From<Arc<T>> for AtomicArc<T> {
+    fn from(arc: Arc<T>) -> Self {
+        Self::new(arc)
+    }
+}
+
+impl<T: Default> Default for AtomicArc<T> {
+    fn default() -> Self {
+        Self::new(Arc::default())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for AtomicArc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("AtomicArc").field(&self.load()).finish()
+    }
+}
@@ -0,0 +1,68 @@
+use core::fmt;
+
+/// The error returned by the fallible `Arc` constructors that aren't given any data (like
+/// [`Arc::try_new_uninit`](crate::Arc::try_new_uninit)) when memory can't be allocated.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct AllocError;
+
+impl fmt::Display for AllocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("memory allocation failed")
+    }
+}
+
+impl core::error::Error for AllocError {}
+
+/// The error returned by the fallible `Arc` constructors that are given the data to store (like
+/// [`Arc::try_new`](crate::Arc::try_new)) when memory can't be allocated, holding on to that data so that it
+/// isn't lost.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct TryNewError<T>(pub(crate) T);
+
+impl<T> TryNewError<T> {
+    /// Gives back the data that couldn't be stored.
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// if let Err(error) = Arc::try_new(String::from("hello")) {
+    ///     assert_eq!(error.into_inner(), "hello");
+    /// }
+    /// ```
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+/// Doesn't print the data (so it's available for any `T`, not just printable ones).
+impl<T> fmt::Debug for TryNewError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("TryNewError").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Display for TryNewError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&AllocError, f)
+    }
+}
+
+impl<T> core::error::Error for TryNewError<T> {}
+
+impl<T> From<TryNewError<T>> for AllocError {
+    fn from(_: TryNewError<T>) -> Self {
+        AllocError
+    }
+}
+
+/// The error returned by [`Arc::try_clone`](crate::Arc::try_clone) when there are already too many references to
+/// the data.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CloneError;
+
+impl fmt::Display for CloneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("too many references")
+    }
+}
+
+impl core::error::Error for CloneError {}
@@ -1,13 +1,68 @@
+#![no_std]
+#![cfg_attr(
+    feature = "nightly",
+    feature(
+        coerce_unsized,
+        dispatch_from_dyn,
+        min_specialization,
+        ptr_metadata,
+        rustc_attrs,
+        set_ptr_value,
+        unsize
+    )
+)]
+// `rustc_attrs` is only used for `#[rustc_unsafe_specialization_marker]`, the way std specializes `Arc`'s `PartialEq`.
+#![cfg_attr(feature = "nightly", allow(internal_features))]
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
+// `self: Arc<Self>` methods need this in the crate declaring them, which here is only the tests.
+#![cfg_attr(all(test, feature = "nightly"), feature(arbitrary_self_types))]
+
+extern crate alloc;
+// Only needed for `Arc::into_inner_blocking` (and the tests).
+#[cfg(any(feature = "std", test))]
+extern crate std;
+
+mod allocator;
 mod arc;
 mod arcdata;
+mod atomic;
+mod error;
+mod projection;
+mod weak;
 pub use arc::*;
-pub use arcdata::*;
+pub use atomic::*;
+pub use error::*;
+pub use projection::*;
+pub use weak::*;
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::boxed::Box;
+    use std::string::{String, ToString};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::vec::Vec;
+    use std::{format, vec};
+
+    /// Counts its drops in the given counter, so each test can use its own and still run in parallel with the
+    /// others. The counter has to be a `static` when the `DetectDrop` must be `'static` (sent to a thread, ...).
+    #[derive(Clone)]
+    struct DetectDrop<'a, T = ()>(&'a AtomicUsize, T);
+
+    impl<'a> DetectDrop<'a> {
+        fn new(num_drops: &'a AtomicUsize) -> Self {
+            Self(num_drops, ())
+        }
+    }
+
+    impl<T> Drop for DetectDrop<'_, T> {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
 
     #[test]
+    #[allow(deprecated)]
     fn no_cloning_deref_test() {
         let arc = Arc::new(42);
         assert_eq!(arc.ref_count(), 1);
@@ -18,17 +73,9 @@ mod tests {
     fn drop_test() {
         static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
 
-        struct DetectDrop;
-
-        impl Drop for DetectDrop {
-            fn drop(&mut self) {
-                NUM_DROPS.fetch_add(1, Ordering::Relaxed);
-            }
-        }
-
         // Create two Arcs sharing an object containing a string
         // and a DetectDrop, to detect when it's dropped.
-        let x = Arc::new(("hello", DetectDrop));
+        let x = Arc::new(("hello", DetectDrop::new(&NUM_DROPS)));
         let y = x.clone();
 
         // Send x to another thread, and use it there.
@@ -53,4 +100,3298 @@ mod tests {
         // the object should've been dropped.
         assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 1);
     }
+
+    #[test]
+    fn weak_does_not_keep_data_alive_test() {
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        let x = Arc::new(DetectDrop::new(&NUM_DROPS));
+        let y = x.clone();
+
+        // One thread keeps creating `Weak`s while the last strong references are dropped.
+        let t = std::thread::spawn(move || {
+            let weaks: Vec<_> = (0..1000).map(|_| Arc::downgrade(&y)).collect();
+            drop(y);
+            weaks
+        });
+        drop(x);
+        let weaks = t.join().unwrap();
+
+        // Every `Arc` is gone, so the data must have been dropped, even though the `Weak`s are still around.
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 1);
+        drop(weaks);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn weak_cycle_test() {
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        struct Parent {
+            _child: Arc<Child>,
+            _detect: DetectDrop<'static>,
+        }
+
+        struct Child {
+            parent: std::sync::Mutex<Option<Weak<Parent>>>,
+            _detect: DetectDrop<'static>,
+        }
+
+        // The child points back at its parent through a `Weak`, so the cycle doesn't keep either alive
+        // (running this under Miri also confirms nothing is leaked).
+        let child = Arc::new(Child {
+            parent: std::sync::Mutex::new(None),
+            _detect: DetectDrop::new(&NUM_DROPS),
+        });
+        let parent = Arc::new(Parent {
+            _child: child.clone(),
+            _detect: DetectDrop::new(&NUM_DROPS),
+        });
+        let weak = Arc::downgrade(&parent);
+        *child.parent.lock().unwrap() = Some(weak.clone());
+        drop(weak);
+
+        drop(child);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 0);
+        drop(parent);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn upgrade_race_test() {
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        let arc = Arc::new(DetectDrop::new(&NUM_DROPS));
+        let weaks: Vec<_> = (0..8).map(|_| Arc::downgrade(&arc)).collect();
+
+        // Many threads race `upgrade` against the last `drop`. Whoever manages to upgrade must see data
+        // that hasn't been dropped, and once upgrading fails it must keep failing.
+        let threads: Vec<_> = weaks
+            .into_iter()
+            .map(|weak| {
+                std::thread::spawn(move || {
+                    for _ in 0..1000 {
+                        match weak.upgrade() {
+                            Some(_arc) => assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 0),
+                            None => {
+                                assert!(weak.upgrade().is_none());
+                                break;
+                            }
+                        }
+                    }
+                })
+            })
+            .collect();
+        drop(arc);
+        for t in threads {
+            t.join().unwrap();
+        }
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn upgrade_never_resurrects_test() {
+        use std::sync::atomic::AtomicBool;
+        use std::sync::Mutex;
+
+        struct Payload {
+            alive: AtomicBool,
+        }
+
+        impl Drop for Payload {
+            fn drop(&mut self) {
+                // The allocation outlives the data while `Weak`s exist, so a resurrected `Arc` would observe this.
+                self.alive.store(false, Ordering::Relaxed);
+            }
+        }
+
+        // Start out with a `Weak` whose data is already gone.
+        let current = Arc::new(Mutex::new(Arc::downgrade(&Arc::new(Payload {
+            alive: AtomicBool::new(true),
+        }))));
+        let done = Arc::new(AtomicBool::new(false));
+
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let current = current.clone();
+                let done = done.clone();
+                std::thread::spawn(move || {
+                    while !done.load(Ordering::Relaxed) {
+                        let weak = current.lock().unwrap().clone();
+                        if let Some(payload) = weak.upgrade() {
+                            assert!(payload.alive.load(Ordering::Relaxed));
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        // The owner keeps dropping the only strong reference and replacing it with a fresh one.
+        for _ in 0..1000 {
+            let payload = Arc::new(Payload {
+                alive: AtomicBool::new(true),
+            });
+            *current.lock().unwrap() = Arc::downgrade(&payload);
+            drop(payload);
+        }
+        done.store(true, Ordering::Relaxed);
+        for t in readers {
+            t.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn counts_test() {
+        let arc = Arc::new(42);
+        let clone = arc.clone();
+        let weak = Arc::downgrade(&arc);
+        assert_eq!(Arc::strong_count(&arc), 2);
+        assert_eq!(Arc::weak_count(&arc), 1);
+
+        drop(clone);
+        assert_eq!(Arc::strong_count(&arc), 1);
+        drop(weak);
+        assert_eq!(Arc::weak_count(&arc), 0);
+    }
+
+    #[test]
+    fn dangling_weak_test() {
+        let weak: Weak<String> = Weak::new();
+        assert!(weak.upgrade().is_none());
+        let clone = weak.clone();
+        assert!(clone.upgrade().is_none());
+        drop(weak);
+        drop(clone);
+
+        // A zero-sized `T` has an alignment of 1, so the sentinel must not rely on `T`'s alignment.
+        let weak: Weak<()> = Weak::default();
+        assert!(weak.clone().upgrade().is_none());
+
+        // A real allocation of a zero-sized `T` must still be told apart from the sentinel.
+        let arc = Arc::new(());
+        let weak = Arc::downgrade(&arc);
+        assert!(weak.clone().upgrade().is_some());
+
+        // A field that starts out empty and is pointed somewhere later (dropping the dangling `Weak`).
+        #[derive(Default)]
+        struct Node {
+            parent: Weak<String>,
+        }
+        let mut node = Node::default();
+        assert!(node.parent.upgrade().is_none());
+        assert_eq!(
+            (node.parent.strong_count(), node.parent.weak_count()),
+            (0, 0)
+        );
+        let parent = Arc::new(String::from("parent"));
+        node.parent = Arc::downgrade(&parent);
+        assert_eq!(*node.parent.upgrade().unwrap(), "parent");
+        assert_eq!(Arc::weak_count(&parent), 1);
+        node.parent = Weak::new();
+        assert_eq!(Arc::weak_count(&parent), 0);
+    }
+
+    #[test]
+    fn weak_ptr_eq_test() {
+        let arc = Arc::new(String::from("observer"));
+        let weak = Arc::downgrade(&arc);
+        // A `Weak` points where the `Arc` it came from does, and to the same allocation as every other `Weak` of it.
+        assert_eq!(weak.as_ptr(), Arc::as_ptr(&arc));
+        assert!(weak.ptr_eq(&Arc::downgrade(&arc.clone())));
+        assert!(Arc::ptr_eq(&weak.upgrade().unwrap(), &arc));
+
+        // Dangling `Weak`s are all equal, but never equal to a real allocation (even of a zero-sized `T`).
+        let dangling: Weak<String> = Weak::new();
+        assert!(dangling.ptr_eq(&Weak::new()));
+        assert!(dangling.ptr_eq(&dangling.clone()));
+        assert!(!dangling.ptr_eq(&weak) && !weak.ptr_eq(&dangling));
+        let unit = Arc::new(());
+        assert!(!Weak::<()>::new().ptr_eq(&Arc::downgrade(&unit)));
+
+        // Deduplicating a list of observers, which keeps working after the data is gone.
+        let other = Arc::new(String::from("other"));
+        let mut observers = vec![
+            weak.clone(),
+            Arc::downgrade(&arc),
+            Arc::downgrade(&other),
+            Arc::downgrade(&other),
+            Weak::new(),
+            Weak::new(),
+        ];
+        drop(other);
+        observers.dedup_by(|a, b| a.ptr_eq(b));
+        assert_eq!(observers.len(), 3);
+        assert!(observers[0].ptr_eq(&weak));
+        assert!(observers[1].upgrade().is_none() && observers[1].strong_count() == 0);
+        assert!(observers[2].ptr_eq(&dangling));
+        assert_eq!(observers[0].as_ptr(), Arc::as_ptr(&arc));
+    }
+
+    #[test]
+    fn weak_counts_test() {
+        let arc = Arc::new(42);
+        let weak = Arc::downgrade(&arc);
+        assert_eq!((weak.strong_count(), weak.weak_count()), (1, 1));
+
+        let clone = arc.clone();
+        let weak_clone = weak.clone();
+        let other_weak = Arc::downgrade(&clone);
+        assert_eq!((weak.strong_count(), weak.weak_count()), (2, 3));
+        assert_eq!((Arc::strong_count(&clone), Arc::weak_count(&clone)), (2, 3));
+
+        drop(weak_clone);
+        drop(arc);
+        assert_eq!((other_weak.strong_count(), other_weak.weak_count()), (1, 2));
+        assert_eq!(Arc::weak_count(&clone), 2);
+
+        drop(clone);
+        assert_eq!((weak.strong_count(), weak.weak_count()), (0, 0));
+        assert_eq!((other_weak.strong_count(), other_weak.weak_count()), (0, 0));
+
+        let dangling = Weak::<i32>::new();
+        assert_eq!((dangling.strong_count(), dangling.weak_count()), (0, 0));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn weak_counts_while_waiting_test() {
+        use std::time::Duration;
+
+        let arc = Arc::new(42);
+        let clone = arc.clone();
+        let weak = Arc::downgrade(&arc);
+        std::thread::scope(|s| {
+            let waiter = s.spawn(|| Arc::into_inner_blocking(arc, None).ok());
+            // Whether or not the waiter has flagged the strong count yet, the flag never shows up in the counts.
+            let deadline = std::time::Instant::now() + Duration::from_millis(20);
+            while std::time::Instant::now() < deadline {
+                assert_eq!((weak.strong_count(), weak.weak_count()), (2, 1));
+                assert_eq!(Arc::strong_count(&clone), 2);
+            }
+            drop(clone);
+            assert_eq!(waiter.join().unwrap(), Some(42));
+        });
+        assert_eq!((weak.strong_count(), weak.weak_count()), (0, 0));
+    }
+
+    #[test]
+    fn try_unwrap_test() {
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        // Shared: the `Arc` comes back untouched.
+        let x = Arc::new(DetectDrop::new(&NUM_DROPS));
+        let y = x.clone();
+        let x = Arc::try_unwrap(x).err().unwrap();
+        assert_eq!(Arc::strong_count(&x), 2);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 0);
+
+        // Unique: the data is moved out without being dropped.
+        drop(y);
+        let data = Arc::try_unwrap(x).ok().unwrap();
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 0);
+        drop(data);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 1);
+
+        // Unique, but with a `Weak` that must not be able to upgrade afterwards.
+        let x = Arc::new(DetectDrop::new(&NUM_DROPS));
+        let weak = Arc::downgrade(&x);
+        let data = Arc::try_unwrap(x).ok().unwrap();
+        assert!(weak.upgrade().is_none());
+        drop(data);
+        drop(weak);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn new_cyclic_test() {
+        struct Node {
+            me: Weak<Node>,
+        }
+
+        let node = Arc::new_cyclic(|me: &Weak<Node>| {
+            // The data doesn't exist yet, so this can't upgrade.
+            assert!(me.upgrade().is_none());
+            assert_eq!(me.strong_count(), 0);
+            Node { me: me.clone() }
+        });
+        let upgraded = node.me.upgrade().unwrap();
+        assert_eq!(Arc::strong_count(&node), 2);
+        drop(upgraded);
+        assert_eq!(Arc::strong_count(&node), 1);
+        assert_eq!(Arc::weak_count(&node), 1);
+    }
+
+    #[test]
+    fn new_cyclic_panic_test() {
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        // The allocation must be freed (which Miri would complain about otherwise) without dropping the
+        // uninitialized data.
+        let result = std::panic::catch_unwind(|| {
+            Arc::<DetectDrop>::new_cyclic(|_| panic!("no data for you"))
+        });
+        assert!(result.is_err());
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn new_cyclic_escaped_weak_test() {
+        use std::sync::Mutex;
+
+        // A `Weak` cloned out of a panicking closure keeps the allocation around, but never upgrades.
+        let escaped = Mutex::new(None);
+        let result = std::panic::catch_unwind(|| {
+            Arc::<String>::new_cyclic(|me| {
+                *escaped.lock().unwrap() = Some(me.clone());
+                panic!("no data for you")
+            })
+        });
+        assert!(result.is_err());
+        let escaped = escaped.into_inner().unwrap().unwrap();
+        assert!(escaped.upgrade().is_none());
+        assert_eq!((escaped.strong_count(), escaped.weak_count()), (0, 0));
+        drop(escaped);
+
+        // Another thread polling a `Weak` handed out during construction only ever sees the finished data.
+        let (sender, receiver) = std::sync::mpsc::channel();
+        // Kept alive until the thread is done, or it could spin forever.
+        let _node = Arc::new_cyclic(|me: &Weak<String>| {
+            sender.send(me.clone()).unwrap();
+            String::from("done")
+        });
+        let t = std::thread::spawn(move || {
+            let weak = receiver.recv().unwrap();
+            loop {
+                if let Some(node) = weak.upgrade() {
+                    assert_eq!(*node, "done");
+                    break;
+                }
+            }
+        });
+        t.join().unwrap();
+
+        // Children pointing back at their parent.
+        struct Parent {
+            children: Vec<Child>,
+        }
+
+        struct Child {
+            parent: Weak<Parent>,
+        }
+
+        let parent = Arc::new_cyclic(|me| Parent {
+            children: (0..3).map(|_| Child { parent: me.clone() }).collect(),
+        });
+        assert_eq!(Arc::weak_count(&parent), 3);
+        for child in &parent.children {
+            assert!(Arc::ptr_eq(&child.parent.upgrade().unwrap(), &parent));
+        }
+    }
+
+    #[test]
+    fn into_inner_with_weak_test() {
+        let arc = Arc::new(String::from("hello"));
+        let clone = arc.clone();
+        let weak = Arc::downgrade(&arc);
+
+        assert_eq!(Arc::into_inner(arc), None);
+        assert_eq!(weak.strong_count(), 1);
+        assert_eq!(Arc::into_inner(clone).as_deref(), Some("hello"));
+
+        // The allocation must still be around for the `Weak`, which can no longer upgrade.
+        assert!(weak.upgrade().is_none());
+        assert_eq!((weak.strong_count(), weak.weak_count()), (0, 0));
+        let weak_clone = weak.clone();
+        drop(weak);
+        assert!(weak_clone.upgrade().is_none());
+    }
+
+    #[test]
+    fn get_mut_vs_upgrade_test() {
+        for _ in 0..100 {
+            let mut arc = Arc::new(0);
+            let clone = arc.clone();
+
+            // The other thread only ever reads the data through upgraded `Weak`s. If `get_mut` ever succeeded
+            // while one of those upgrades also did, it would see the write below (and Miri/TSan would flag the race).
+            let t = std::thread::spawn(move || {
+                let weak = Arc::downgrade(&clone);
+                drop(clone);
+                for _ in 0..100 {
+                    if let Some(upgraded) = weak.upgrade() {
+                        assert_eq!(*upgraded, 0);
+                    }
+                }
+            });
+            loop {
+                if let Some(data) = Arc::get_mut(&mut arc) {
+                    *data = 1;
+                    break;
+                }
+                std::hint::spin_loop();
+            }
+            t.join().unwrap();
+            assert_eq!(Arc::weak_count(&arc), 0);
+        }
+    }
+
+    #[test]
+    fn make_mut_test() {
+        // Unique: mutated in place.
+        let mut arc = Arc::new(1);
+        let before: *const i32 = &*arc;
+        *Arc::make_mut(&mut arc) += 1;
+        assert_eq!(*arc, 2);
+        assert_eq!(before, &*arc as *const i32);
+
+        // Shared: cloned, the other `Arc` still sees the old value.
+        let clone = arc.clone();
+        *Arc::make_mut(&mut arc) += 1;
+        assert_eq!((*arc, *clone), (3, 2));
+        assert_eq!((Arc::strong_count(&arc), Arc::strong_count(&clone)), (1, 1));
+        drop(clone);
+
+        // Only `Weak`s left: moved into a new allocation, and the `Weak` can't upgrade anymore.
+        let weak = Arc::downgrade(&arc);
+        *Arc::make_mut(&mut arc) += 1;
+        assert_eq!(*arc, 4);
+        assert!(weak.upgrade().is_none());
+        assert_eq!(Arc::weak_count(&arc), 0);
+    }
+
+    #[test]
+    fn weak_raw_round_trip_test() {
+        // Live.
+        let arc = Arc::new(String::from("hello"));
+        let weak = Arc::downgrade(&arc);
+        let ptr = weak.into_raw();
+        assert_eq!(unsafe { &*ptr }, "hello");
+        // Neither count changes while the weak reference is just a pointer.
+        assert_eq!(Arc::weak_count(&arc), 1);
+        assert_eq!(Arc::strong_count(&arc), 1);
+        let weak = unsafe { Weak::from_raw(ptr) };
+        assert_eq!(weak.as_ptr(), ptr);
+        assert_eq!(Arc::weak_count(&arc), 1);
+        assert_eq!(Arc::strong_count(&arc), 1);
+
+        // Dead, but still weakly held.
+        drop(arc);
+        let ptr = weak.into_raw();
+        let weak = unsafe { Weak::from_raw(ptr) };
+        assert!(weak.upgrade().is_none());
+        drop(weak);
+
+        // Dangling.
+        let ptr = Weak::<String>::new().into_raw();
+        assert_eq!(ptr, Weak::<String>::new().as_ptr());
+        let weak = unsafe { Weak::from_raw(ptr) };
+        assert_eq!(weak.as_ptr(), ptr);
+        assert!(weak.upgrade().is_none());
+        assert!(weak.ptr_eq(&Weak::new()));
+    }
+
+    #[test]
+    fn get_mut_with_weak_test() {
+        let mut arc = Arc::new(0);
+        let weak = Arc::downgrade(&arc);
+        // The `Weak` could be upgraded at any time, so handing out a mutable reference would be unsound.
+        assert!(Arc::get_mut(&mut arc).is_none());
+
+        drop(weak);
+        *Arc::get_mut(&mut arc).unwrap() = 42;
+        assert_eq!(*arc, 42);
+
+        // A dangling `Weak` has nothing to do with this allocation.
+        let _dangling = Weak::<i32>::new();
+        assert!(Arc::get_mut(&mut arc).is_some());
+    }
+
+    #[test]
+    fn weak_clone_across_threads_test() {
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        let arc = Arc::new(DetectDrop::new(&NUM_DROPS));
+        let weak = Arc::downgrade(&arc);
+        drop(arc);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 1);
+
+        // The data is gone, but the allocation must survive until the last of these clones is dropped
+        // (and be freed exactly once, which Miri checks).
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let weak = weak.clone();
+                std::thread::spawn(move || {
+                    let clones: Vec<_> = (0..100).map(|_| weak.clone()).collect();
+                    assert!(clones.iter().all(|clone| clone.ptr_eq(&weak)));
+                    assert!(clones.iter().all(|clone| clone.upgrade().is_none()));
+                })
+            })
+            .collect();
+        drop(weak);
+        for t in threads {
+            t.join().unwrap();
+        }
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 1);
+        assert_eq!(format!("{:?}", Weak::<DetectDrop>::new()), "(Weak)");
+    }
+
+    #[test]
+    fn ptr_eq_test() {
+        let arc = Arc::new(String::from("hello"));
+        let clone = arc.clone();
+        let other = Arc::new(String::from("hello"));
+        assert!(Arc::ptr_eq(&arc, &clone));
+        // Equal data, but a different allocation.
+        assert_eq!(*arc, *other);
+        assert!(!Arc::ptr_eq(&arc, &other));
+        // An upgraded `Weak` points to the same allocation it was downgraded from.
+        assert!(Arc::ptr_eq(
+            &Arc::downgrade(&other).upgrade().unwrap(),
+            &other
+        ));
+    }
+
+    #[test]
+    fn panicking_drop_test() {
+        struct PanicOnDrop;
+
+        impl Drop for PanicOnDrop {
+            fn drop(&mut self) {
+                panic!("oops");
+            }
+        }
+
+        let arc = Arc::new(PanicOnDrop);
+        let weak = Arc::downgrade(&arc);
+        assert!(std::panic::catch_unwind(move || drop(arc)).is_err());
+        // The data is gone, and dropping the last `Weak` must still free the allocation (Miri checks for leaks).
+        assert!(weak.upgrade().is_none());
+        drop(weak);
+
+        // Same without any `Weak`s: the allocation is freed during unwinding.
+        let arc = Arc::new(PanicOnDrop);
+        assert!(std::panic::catch_unwind(move || drop(arc)).is_err());
+    }
+
+    #[test]
+    fn zero_sized_drop_test() {
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        // Zero-sized, unlike the shared `DetectDrop`.
+        struct ZeroSizedDetectDrop;
+
+        impl Drop for ZeroSizedDetectDrop {
+            fn drop(&mut self) {
+                NUM_DROPS.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        assert_eq!(std::mem::size_of::<ZeroSizedDetectDrop>(), 0);
+        let arc = Arc::new(ZeroSizedDetectDrop);
+        let weak = Arc::downgrade(&arc);
+        drop(arc);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 1);
+        drop(weak);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn last_strong_and_last_weak_race_test() {
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        // Race the last `Arc` and the last `Weak` being dropped on different threads: the data must be dropped
+        // exactly once, and the allocation freed exactly once (by whichever goes last).
+        for i in 0..1000 {
+            let arc = Arc::new(DetectDrop::new(&NUM_DROPS));
+            let weak = Arc::downgrade(&arc);
+            let t = std::thread::spawn(move || drop(weak));
+            drop(arc);
+            t.join().unwrap();
+            assert_eq!(NUM_DROPS.load(Ordering::Relaxed), i + 1);
+        }
+    }
+
+    #[test]
+    fn as_ptr_test() {
+        let arc = Arc::new(String::from("hello"));
+        let clone = arc.clone();
+        let ptr = Arc::as_ptr(&arc);
+        assert_eq!(ptr, Arc::as_ptr(&clone));
+        assert_eq!(ptr, &*arc as *const String);
+        assert_eq!(unsafe { &*ptr }, "hello");
+        // Taking the pointer doesn't change the counts.
+        assert_eq!(Arc::strong_count(&arc), 2);
+        // `Weak::as_ptr` agrees with `Arc::as_ptr`.
+        assert_eq!(Arc::downgrade(&arc).as_ptr(), ptr);
+    }
+
+    #[test]
+    fn try_unwrap_contended_test() {
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        for i in 0..1000 {
+            let arc = Arc::new(DetectDrop::new(&NUM_DROPS));
+            let clone = arc.clone();
+            let t = std::thread::spawn(move || drop(clone));
+            // Depending on who wins, we either get the data, or the untouched `Arc` back. Either way, the data
+            // is dropped exactly once.
+            let unwrapped = Arc::try_unwrap(arc);
+            t.join().unwrap();
+            match unwrapped {
+                Ok(data) => drop(data),
+                Err(arc) => {
+                    assert_eq!(Arc::strong_count(&arc), 1);
+                    assert_eq!(NUM_DROPS.load(Ordering::Relaxed), i);
+                    drop(arc);
+                }
+            }
+            assert_eq!(NUM_DROPS.load(Ordering::Relaxed), i + 1);
+        }
+    }
+
+    #[test]
+    fn raw_round_trip_test() {
+        let arc = Arc::new(String::from("hello"));
+        let clone = arc.clone();
+        let ptr = Arc::into_raw(arc);
+        assert_eq!(ptr, Arc::as_ptr(&clone));
+        // `into_raw` leaves the count alone, so the raw pointer still counts as a strong reference.
+        assert_eq!(Arc::strong_count(&clone), 2);
+
+        let arc = unsafe { Arc::from_raw(ptr) };
+        assert_eq!(*arc, "hello");
+        assert!(Arc::ptr_eq(&arc, &clone));
+        drop(arc);
+        assert_eq!(Arc::strong_count(&clone), 1);
+    }
+
+    #[test]
+    fn into_inner_race_test() {
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        for i in 0..100 {
+            let arc = Arc::new(DetectDrop::new(&NUM_DROPS));
+            let threads: Vec<_> = (0..8)
+                .map(|_| {
+                    let clone = arc.clone();
+                    std::thread::spawn(move || Arc::into_inner(clone))
+                })
+                .collect();
+            let mine = Arc::into_inner(arc);
+
+            // Exactly one of the handles gets the data; nobody drops it along the way.
+            let results: Vec<_> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+            let winners = results
+                .iter()
+                .chain([&mine])
+                .filter(|r| r.is_some())
+                .count();
+            assert_eq!(winners, 1);
+            assert_eq!(NUM_DROPS.load(Ordering::Relaxed), i);
+            drop(results);
+            drop(mine);
+            assert_eq!(NUM_DROPS.load(Ordering::Relaxed), i + 1);
+        }
+    }
+
+    #[test]
+    fn raw_strong_count_test() {
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        let ptr = Arc::into_raw(Arc::new(DetectDrop::new(&NUM_DROPS)));
+        unsafe { Arc::increment_strong_count(ptr) };
+
+        // Each strong reference the raw pointer stands for is reconstituted exactly once.
+        let first = unsafe { Arc::from_raw(ptr) };
+        let second = unsafe { Arc::from_raw(ptr) };
+        assert_eq!(Arc::strong_count(&first), 2);
+        drop(first);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 0);
+        drop(second);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 1);
+
+        // `decrement_strong_count` runs the full drop logic on the last reference.
+        let ptr = Arc::into_raw(Arc::new(DetectDrop::new(&NUM_DROPS)));
+        unsafe { Arc::decrement_strong_count(ptr) };
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn make_mut_clones_only_when_shared_test() {
+        static NUM_CLONES: AtomicUsize = AtomicUsize::new(0);
+
+        struct DetectClone(i32);
+
+        impl Clone for DetectClone {
+            fn clone(&self) -> Self {
+                NUM_CLONES.fetch_add(1, Ordering::Relaxed);
+                Self(self.0)
+            }
+        }
+
+        // Unique: no clone.
+        let mut arc = Arc::new(DetectClone(0));
+        Arc::make_mut(&mut arc).0 += 1;
+        assert_eq!(NUM_CLONES.load(Ordering::Relaxed), 0);
+
+        // Strong-unique, but with a `Weak`: moved to a new allocation, still no clone.
+        let weak = Arc::downgrade(&arc);
+        Arc::make_mut(&mut arc).0 += 1;
+        assert_eq!(NUM_CLONES.load(Ordering::Relaxed), 0);
+        assert_eq!((weak.strong_count(), Arc::weak_count(&arc)), (0, 0));
+
+        // Shared: exactly one clone, and the old allocation keeps its other strong reference.
+        let other = arc.clone();
+        Arc::make_mut(&mut arc).0 += 1;
+        assert_eq!(NUM_CLONES.load(Ordering::Relaxed), 1);
+        assert_eq!((arc.0, other.0), (3, 2));
+        assert_eq!(Arc::strong_count(&other), 1);
+    }
+
+    #[test]
+    fn make_mut_slice_test() {
+        static NUM_CLONES: AtomicUsize = AtomicUsize::new(0);
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        struct Detect(i32, DetectDrop<'static>);
+
+        impl Clone for Detect {
+            fn clone(&self) -> Self {
+                // Only the fourth clone panics, after the third went through.
+                if NUM_CLONES.fetch_add(1, Ordering::Relaxed) == 3 {
+                    panic!("clone failed");
+                }
+                Self(self.0, self.1.clone())
+            }
+        }
+
+        // Unique: the same elements, in the same place, without allocating.
+        let mut arc: Arc<[Detect]> = Arc::from(vec![
+            Detect(0, DetectDrop::new(&NUM_DROPS)),
+            Detect(1, DetectDrop::new(&NUM_DROPS)),
+        ]);
+        let ptr = arc.as_ptr();
+        let before = allocations();
+        Arc::make_mut(&mut arc)[0].0 = 10;
+        assert_eq!(allocations(), before);
+        assert_eq!(arc.as_ptr(), ptr);
+
+        // Strong-unique, but with a `Weak`: moved to a new allocation, without cloning.
+        let weak = Arc::downgrade(&arc);
+        Arc::make_mut(&mut arc)[1].0 = 11;
+        assert_ne!(arc.as_ptr(), ptr);
+        assert!(weak.upgrade().is_none());
+        drop(weak);
+        assert_eq!(NUM_CLONES.load(Ordering::Relaxed), 0);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 0);
+        assert_eq!((arc[0].0, arc[1].0), (10, 11));
+
+        // Shared: cloned into a new allocation, while the old one stays just as it was for the other holder.
+        let other = arc.clone();
+        let ptr = other.as_ptr();
+        Arc::make_mut(&mut arc)[0].0 = 20;
+        assert_eq!(NUM_CLONES.load(Ordering::Relaxed), 2);
+        assert_eq!((arc[0].0, other[0].0), (20, 10));
+        assert_eq!(other.as_ptr(), ptr);
+        assert_eq!(Arc::strong_count(&other), 1);
+        assert_eq!(Arc::strong_count(&arc), 1);
+
+        // A clone panicking partway through drops the clones made so far, and leaves `arc` alone.
+        let mut arc = other.clone();
+        let result = std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| {
+            Arc::make_mut(&mut arc);
+        }));
+        assert!(result.is_err());
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 1);
+        assert!(Arc::ptr_eq(&arc, &other));
+        assert_eq!(Arc::strong_count(&other), 2);
+        Arc::make_mut(&mut arc);
+        assert!(!Arc::ptr_eq(&arc, &other));
+
+        // `str`s, unique and shared.
+        let mut s: Arc<str> = Arc::from("hello");
+        let ptr = s.as_ptr();
+        Arc::make_mut(&mut s).make_ascii_uppercase();
+        assert_eq!((&*s, s.as_ptr()), ("HELLO", ptr));
+        let other = s.clone();
+        Arc::make_mut(&mut s).make_ascii_lowercase();
+        assert_eq!((&*s, &*other), ("hello", "HELLO"));
+
+        // The shared empty default is never handed out mutably.
+        let mut empty: Arc<[u8]> = Arc::default();
+        let mut empty_str: Arc<str> = Arc::default();
+        assert!(Arc::make_mut(&mut empty).is_empty());
+        assert!(Arc::make_mut(&mut empty_str).is_empty());
+        assert!(!Arc::ptr_eq(&empty, &Arc::default()));
+        assert!(Arc::get_mut(&mut empty).is_some());
+        assert!(Arc::get_mut(&mut empty_str).is_some());
+    }
+
+    #[test]
+    fn unwrap_or_clone_test() {
+        static NUM_CLONES: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(Debug, PartialEq)]
+        struct DetectClone(i32);
+
+        impl Clone for DetectClone {
+            fn clone(&self) -> Self {
+                NUM_CLONES.fetch_add(1, Ordering::Relaxed);
+                Self(self.0)
+            }
+        }
+
+        let arc = Arc::new(DetectClone(42));
+        let clone = arc.clone();
+        assert_eq!(Arc::unwrap_or_clone(arc), DetectClone(42));
+        assert_eq!(NUM_CLONES.load(Ordering::Relaxed), 1);
+        assert_eq!(Arc::unwrap_or_clone(clone), DetectClone(42));
+        assert_eq!(NUM_CLONES.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn unwrap_or_clone_after_threads_test() {
+        static NUM_CLONES: AtomicUsize = AtomicUsize::new(0);
+
+        struct Buffer(Vec<u8>);
+
+        impl Clone for Buffer {
+            fn clone(&self) -> Self {
+                NUM_CLONES.fetch_add(1, Ordering::Relaxed);
+                Self(self.0.clone())
+            }
+        }
+
+        // Workers read the shared buffer and drop their handles on other threads.
+        let arc = Arc::new(Buffer(vec![1, 2, 3]));
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let clone = arc.clone();
+                std::thread::spawn(move || clone.0.iter().map(|&b| b as usize).sum::<usize>())
+            })
+            .collect();
+        for t in threads {
+            assert_eq!(t.join().unwrap(), 6);
+        }
+
+        // Every other handle is gone, so the buffer is moved out without a clone.
+        let buffer = Arc::unwrap_or_clone(arc);
+        assert_eq!(buffer.0, [1, 2, 3]);
+        assert_eq!(NUM_CLONES.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn ptr_eq_zero_sized_test() {
+        // Pointers to distinct zero-sized values may compare equal, but the counts live in the same allocation
+        // as the data, so distinct `Arc`s of a zero-sized type still don't.
+        let a = Arc::new(());
+        let b = Arc::new(());
+        assert!(!Arc::ptr_eq(&a, &b));
+        assert!(Arc::ptr_eq(&a, &a.clone()));
+    }
+
+    #[test]
+    fn pointer_fmt_test() {
+        let arc = Arc::new(42);
+        let clone = arc.clone();
+        assert_eq!(format!("{arc:p}"), format!("{clone:p}"));
+        assert_ne!(format!("{arc:p}"), format!("{:p}", Arc::new(42)));
+
+        let s: Arc<str> = Arc::from("hello");
+        assert_eq!(format!("{s:p}"), format!("{:p}", Arc::as_ptr(&s)));
+    }
+
+    #[test]
+    fn debug_display_fmt_test() {
+        #[derive(Debug)]
+        #[allow(dead_code)]
+        struct Config {
+            name: Arc<str>,
+            ports: Arc<[u16]>,
+            weight: Arc<f64>,
+        }
+
+        let config = Config {
+            name: Arc::from("server"),
+            ports: Arc::from([80, 443]),
+            weight: Arc::new(0.5),
+        };
+        // Exactly as if there were no `Arc`s around the fields, alternate form included.
+        let expected = r#"Config { name: "server", ports: [80, 443], weight: 0.5 }"#;
+        assert_eq!(format!("{config:?}"), expected);
+        let expected = "Config {\n    name: \"server\",\n    ports: [\n        80,\n        443,\n    ],\n    weight: 0.5,\n}";
+        assert_eq!(format!("{config:#?}"), expected);
+
+        // Flags are passed on too.
+        assert_eq!(format!("{:<8}|", config.name), "server  |");
+        assert_eq!(format!("{:.2}", config.weight), "0.50");
+        assert_eq!(format!("{:?}", Arc::<[u8]>::default()), "[]");
+        assert_eq!(
+            format!("{:?}", Arc::<core::ffi::CStr>::from(c"hi")),
+            "\"hi\""
+        );
+    }
+
+    #[test]
+    fn as_ptr_stable_across_moves_test() {
+        let arc = Arc::new([0u8; 64]);
+        let ptr = Arc::as_ptr(&arc);
+
+        // Moving the handle around (into a `Box`, another thread, ...) doesn't move the data.
+        let boxed = Box::new(arc);
+        assert_eq!(Arc::as_ptr(&boxed), ptr);
+        let addr = ptr as usize;
+        let arc = std::thread::spawn(move || {
+            assert_eq!(Arc::as_ptr(&boxed) as usize, addr);
+            *boxed
+        })
+        .join()
+        .unwrap();
+        assert_eq!(Arc::as_ptr(&arc), ptr);
+        assert_eq!(Arc::into_raw(arc), ptr);
+        drop(unsafe { Arc::from_raw(ptr) });
+    }
+
+    #[test]
+    fn raw_round_trip_alignment_test() {
+        fn round_trip<T: PartialEq + std::fmt::Debug + Clone>(value: T) {
+            let arc = Arc::new(value.clone());
+            let leaked = arc.clone();
+            let ptr = Arc::into_raw(leaked);
+            assert_eq!(ptr.align_offset(std::mem::align_of::<T>()), 0);
+            assert_eq!(unsafe { &*ptr }, &value);
+            assert_eq!(Arc::strong_count(&arc), 2);
+
+            let restored = unsafe { Arc::from_raw(ptr) };
+            assert!(Arc::ptr_eq(&restored, &arc));
+            drop(restored);
+            assert_eq!(Arc::strong_count(&arc), 1);
+        }
+
+        #[derive(PartialEq, Debug, Clone)]
+        #[repr(align(64))]
+        struct CacheLine(u8);
+
+        #[derive(PartialEq, Debug, Clone)]
+        #[repr(align(4096))]
+        struct Page(u8);
+
+        round_trip(1u8);
+        round_trip(2u16);
+        round_trip(3u128);
+        round_trip(());
+        round_trip(CacheLine(4));
+        round_trip(Page(5));
+    }
+
+    #[test]
+    fn comparisons_test() {
+        let a = Arc::new(String::from("a"));
+        let b = Arc::new(String::from("b"));
+        // Same allocation.
+        assert_eq!(a, a.clone());
+        // Equal data in distinct allocations.
+        assert_eq!(a, Arc::new(String::from("a")));
+        assert_ne!(a, b);
+        assert!(a < b);
+        assert_eq!(a.cmp(&b), std::cmp::Ordering::Less);
+
+        // Usable as `BTreeMap` keys.
+        let map: std::collections::BTreeMap<_, _> =
+            [(b.clone(), 2), (a.clone(), 1)].into_iter().collect();
+        assert_eq!(map.keys().next(), Some(&a));
+        assert_eq!(map[&Arc::new(String::from("b"))], 2);
+    }
+
+    #[test]
+    #[cfg(feature = "nightly")]
+    fn eq_same_allocation_test() {
+        static NUM_EQS: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(Eq)]
+        struct CountEqs(u32);
+        impl PartialEq for CountEqs {
+            fn eq(&self, other: &Self) -> bool {
+                NUM_EQS.fetch_add(1, Ordering::Relaxed);
+                self.0 == other.0
+            }
+        }
+
+        let arc = Arc::new(CountEqs(1));
+        assert!(arc == arc.clone());
+        assert!(!(arc != arc.clone()));
+        assert_eq!(NUM_EQS.load(Ordering::Relaxed), 0);
+        // Distinct allocations still compare the data.
+        assert!(arc == Arc::new(CountEqs(1)));
+        assert!(arc != Arc::new(CountEqs(2)));
+        assert_eq!(NUM_EQS.load(Ordering::Relaxed), 2);
+
+        // Without `Eq` nothing is assumed, even for the same allocation.
+        let nan = Arc::new(f64::NAN);
+        assert!(nan != nan.clone());
+    }
+
+    #[test]
+    fn hash_test() {
+        let mut set = std::collections::HashSet::new();
+        // Distinct allocations with equal contents are the same key.
+        set.insert(Arc::new(String::from("hello")));
+        set.insert(Arc::new(String::from("hello")));
+        assert_eq!(set.len(), 1);
+        assert!(set.contains(&Arc::new(String::from("hello"))));
+    }
+
+    #[test]
+    fn raw_strong_count_ffi_test() {
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        // Leak once to "C", which then duplicates its handle twice.
+        let ptr = Arc::into_raw(Arc::new(DetectDrop::new(&NUM_DROPS)));
+        unsafe {
+            Arc::increment_strong_count(ptr);
+            Arc::increment_strong_count(ptr);
+        }
+
+        // Each of the three handles is released on a different thread.
+        let addr = ptr as usize;
+        let threads: Vec<_> = (0..3)
+            .map(|_| {
+                std::thread::spawn(move || {
+                    drop(unsafe { Arc::from_raw(addr as *const DetectDrop) })
+                })
+            })
+            .collect();
+        for t in threads {
+            t.join().unwrap();
+        }
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn get_mut_unchecked_test() {
+        // Initialize a large buffer in place before it's ever shared.
+        let mut arc = Arc::new(vec![0u64; 1 << 16]);
+        let buffer = unsafe { Arc::get_mut_unchecked(&mut arc) };
+        for (i, x) in buffer.iter_mut().enumerate() {
+            *x = i as u64;
+        }
+
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let arc = arc.clone();
+                std::thread::spawn(move || arc.iter().sum::<u64>())
+            })
+            .collect();
+        let expected = (0..1u64 << 16).sum::<u64>();
+        for t in threads {
+            assert_eq!(t.join().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn from_box_test() {
+        static NUM_CLONES: AtomicUsize = AtomicUsize::new(0);
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        struct Detect(DetectDrop<'static>);
+
+        impl Clone for Detect {
+            fn clone(&self) -> Self {
+                NUM_CLONES.fetch_add(1, Ordering::Relaxed);
+                Self(self.0.clone())
+            }
+        }
+
+        let detect = || Detect(DetectDrop::new(&NUM_DROPS));
+
+        // The value is moved out of the `Box` (whose memory is freed, as Miri checks), never cloned nor dropped.
+        let arc: Arc<Detect> = Box::new(detect()).into();
+        assert_eq!(NUM_CLONES.load(Ordering::Relaxed), 0);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 0);
+        drop(arc);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 1);
+
+        let arc: Arc<Detect> = detect().into();
+        assert_eq!(NUM_CLONES.load(Ordering::Relaxed), 0);
+        drop(arc);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 2);
+
+        // Same for the elements of a boxed slice: each is moved over, then dropped once along with the `Arc`.
+        let boxed: Box<[Detect]> = vec![detect(), detect(), detect()].into_boxed_slice();
+        let arc: Arc<[Detect]> = boxed.into();
+        assert_eq!(arc.len(), 3);
+        assert_eq!(NUM_CLONES.load(Ordering::Relaxed), 0);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 2);
+        drop(arc);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 5);
+
+        let empty: Arc<[Detect]> = Box::<[Detect]>::default().into();
+        assert!(empty.is_empty());
+
+        let boxed: Box<str> = "héllo".into();
+        let before = (allocations(), deallocations());
+        let arc: Arc<str> = boxed.into();
+        // A new allocation, and the `Box`'s freed.
+        assert_eq!(allocations(), before.0 + 1);
+        assert_eq!(deallocations(), before.1 + 1);
+        assert_eq!(&*arc, "héllo");
+        let arc: Arc<str> = Box::<str>::default().into();
+        assert_eq!(&*arc, "");
+    }
+
+    #[test]
+    #[cfg(feature = "nightly")]
+    fn from_box_dyn_test() {
+        use std::any::Any;
+        use std::error::Error;
+        use std::fmt::Display;
+
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        trait Shape {
+            fn area(&self) -> u32;
+        }
+
+        // More aligned than the header, so the data doesn't start right after it.
+        #[repr(align(128))]
+        struct Square {
+            side: u32,
+            _detect: DetectDrop<'static>,
+        }
+
+        impl Shape for Square {
+            fn area(&self) -> u32 {
+                self.side * self.side
+            }
+        }
+
+        struct Point;
+
+        impl Shape for Point {
+            fn area(&self) -> u32 {
+                0
+            }
+        }
+
+        // The value is moved over (the vtable along with it) without being dropped, and dropped once at the end.
+        let boxed: Box<dyn Shape> = Box::new(Square {
+            side: 3,
+            _detect: DetectDrop::new(&NUM_DROPS),
+        });
+        let arc: Arc<dyn Shape> = Arc::from(boxed);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 0);
+        assert_eq!(arc.area(), 9);
+        assert_eq!(Arc::as_ptr(&arc).cast::<u8>() as usize % 128, 0);
+        let weak = Arc::downgrade(&arc);
+        let clone = weak.upgrade().unwrap();
+        drop(arc);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 0);
+        drop(clone);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 1);
+        assert!(weak.upgrade().is_none());
+
+        // A zero-sized value, whose `Box` never allocated.
+        let arc: Arc<dyn Shape> = Arc::from(Box::new(Point) as Box<dyn Shape>);
+        assert_eq!(arc.area(), 0);
+
+        let arc: Arc<dyn Any + Send + Sync> = Arc::from(Box::new(42) as Box<dyn Any + Send + Sync>);
+        assert_eq!(*arc.downcast::<i32>().ok().unwrap(), 42);
+        let arc: Arc<dyn Display> = Arc::from(Box::new("hello") as Box<dyn Display>);
+        assert_eq!(arc.to_string(), "hello");
+
+        // The usual way errors are passed around can be shared too.
+        let boxed: Box<dyn Error + Send + Sync> = "oops".into();
+        let arc: Arc<dyn Error + Send + Sync> = Arc::from(boxed);
+        assert_eq!(arc.to_string(), "oops");
+        assert!(arc.source().is_none());
+    }
+
+    #[test]
+    fn pin_test() {
+        fn assert_unpin<T: Unpin>() {}
+        // The handle is `Unpin` even when the data isn't.
+        assert_unpin::<Arc<std::marker::PhantomPinned>>();
+
+        let pinned = Arc::pin(std::marker::PhantomPinned);
+        let clone = pinned.clone();
+        assert_eq!(
+            &*pinned as *const std::marker::PhantomPinned,
+            &*clone as *const std::marker::PhantomPinned
+        );
+
+        let pinned: std::pin::Pin<Arc<i32>> = Arc::new(42).into();
+        assert_eq!(*pinned, 42);
+        assert_eq!(Arc::into_inner(std::pin::Pin::into_inner(pinned)), Some(42));
+    }
+
+    #[test]
+    fn as_ref_borrow_test() {
+        fn len(s: impl AsRef<String>) -> usize {
+            s.as_ref().len()
+        }
+
+        let arc = Arc::new(String::from("hello"));
+        assert_eq!(len(arc.clone()), 5);
+
+        let set: std::collections::BTreeSet<_> = [arc].into_iter().collect();
+        assert!(set.contains(&String::from("hello")));
+    }
+
+    #[test]
+    fn try_new_test() {
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        // When allocating fails, the data comes back whole, and is dropped exactly once: when we drop it. It owns
+        // heap memory of its own, so running the tests under miri also catches it being leaked.
+        arcdata::FAIL_ALLOCATIONS.set(true);
+        let result = Arc::try_new(DetectDrop(&NUM_DROPS, vec![1, 2, 3]));
+        arcdata::FAIL_ALLOCATIONS.set(false);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 0);
+        let data = result.err().unwrap().into_inner();
+        assert_eq!(data.1, [1, 2, 3]);
+        drop(data);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 1);
+
+        let arc = Arc::try_new(DetectDrop(&NUM_DROPS, vec![4, 2])).unwrap();
+        assert_eq!(arc.1, [4, 2]);
+        let weak = Arc::downgrade(&arc);
+        drop(arc);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 2);
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[test]
+    fn try_pin_test() {
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        // When allocating fails, the value that was about to be stored comes back, and is only dropped by us.
+        arcdata::FAIL_ALLOCATIONS.set(true);
+        let result = Arc::try_pin(DetectDrop::new(&NUM_DROPS));
+        arcdata::FAIL_ALLOCATIONS.set(false);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 0);
+        drop(result.err().unwrap().into_inner());
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 1);
+
+        let pinned = Arc::try_pin(DetectDrop::new(&NUM_DROPS)).unwrap();
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 1);
+        drop(pinned);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn error_source_test() {
+        use std::error::Error;
+        use std::fmt;
+
+        #[derive(Debug)]
+        struct Inner;
+
+        impl fmt::Display for Inner {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("inner")
+            }
+        }
+
+        impl Error for Inner {}
+
+        #[derive(Debug)]
+        struct Outer(Inner);
+
+        impl fmt::Display for Outer {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("outer")
+            }
+        }
+
+        impl Error for Outer {
+            fn source(&self) -> Option<&(dyn Error + 'static)> {
+                Some(&self.0)
+            }
+        }
+
+        let arc = Arc::new(Outer(Inner));
+        assert_eq!(arc.to_string(), "outer");
+        let source = arc.source().unwrap();
+        assert_eq!(source.to_string(), "inner");
+        assert!(source.is::<Inner>());
+        assert!(source.source().is_none());
+
+        // Also works behind a `Box<dyn Error>`.
+        let boxed: Box<dyn Error> = Box::new(arc.clone());
+        assert_eq!(boxed.source().unwrap().to_string(), "inner");
+
+        #[cfg(feature = "nightly")]
+        {
+            // A type-erased error (needing the unsized coercion), which can be shared between threads and is an
+            // error itself.
+            let shared: Arc<dyn Error + Send + Sync> = arc;
+            let chain: Vec<_> =
+                std::iter::successors(Some(&shared as &(dyn Error + 'static)), |&e| e.source())
+                    .map(ToString::to_string)
+                    .collect();
+            assert_eq!(chain, ["outer", "inner"]);
+            assert!(shared.source().unwrap().is::<Inner>());
+
+            let boxed: Box<dyn Error + Send + Sync> = Box::new(shared.clone());
+            assert_eq!(boxed.to_string(), "outer");
+            assert_eq!(boxed.source().unwrap().to_string(), "inner");
+            std::thread::spawn(move || assert_eq!(shared.to_string(), "outer"))
+                .join()
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn new_uninit_test() {
+        let mut arc = Arc::<[u32; 1024]>::new_uninit();
+        let before = Arc::as_ptr(&arc) as usize;
+        let uninit = Arc::get_mut(&mut arc).unwrap();
+        for i in 0..1024 {
+            unsafe { (*uninit.as_mut_ptr())[i] = i as u32 };
+        }
+        let clone = arc.clone();
+
+        let arc = unsafe { arc.assume_init() };
+        // Same allocation, same counts.
+        assert_eq!(Arc::as_ptr(&arc) as usize, before);
+        assert_eq!(Arc::strong_count(&arc), 2);
+        assert!(arc.iter().enumerate().all(|(i, &x)| x == i as u32));
+        drop(clone);
+        assert_eq!(Arc::strong_count(&arc), 1);
+    }
+
+    #[test]
+    fn new_uninit_get_mut_test() {
+        let mut arc = Arc::<u64>::new_uninit();
+        let weak = Arc::downgrade(&arc);
+        // `get_mut` refuses while the `Weak` exists, like for any other `Arc`.
+        assert!(Arc::get_mut(&mut arc).is_none());
+        drop(weak);
+        Arc::get_mut(&mut arc).unwrap().write(42);
+
+        let before = Arc::as_ptr(&arc).cast::<u64>();
+        let arc = unsafe { arc.assume_init() };
+        assert_eq!(*arc, 42);
+        assert_eq!(Arc::as_ptr(&arc), before);
+        assert_eq!((Arc::strong_count(&arc), Arc::weak_count(&arc)), (1, 0));
+    }
+
+    #[test]
+    fn slice_from_vec_test() {
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        let arc: Arc<[DetectDrop<u8>]> = Arc::from(
+            (0..10)
+                .map(|i| DetectDrop(&NUM_DROPS, i))
+                .collect::<Vec<_>>(),
+        );
+        // Moving the elements out of the `Vec` must not drop them.
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 0);
+        assert_eq!(arc.len(), 10);
+        assert!(arc.iter().enumerate().all(|(i, x)| x.1 == i as u8));
+
+        let weak = Arc::downgrade(&arc);
+        let clone = arc.clone();
+        drop(arc);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 0);
+        assert_eq!(weak.upgrade().unwrap().len(), 10);
+        drop(clone);
+        // Every element is dropped exactly once, while the `Weak` keeps the allocation around.
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 10);
+        assert!(weak.upgrade().is_none());
+        drop(weak);
+
+        // Elements with a bigger alignment than the reference counts.
+        #[repr(align(64))]
+        struct Aligned(u8);
+        let arc: Arc<[Aligned]> = Arc::from(vec![Aligned(4), Aligned(2)]);
+        assert_eq!(Arc::as_ptr(&arc).cast::<Aligned>() as usize % 64, 0);
+        assert_eq!((arc[0].0, arc[1].0), (4, 2));
+
+        // Formatting goes through to the slice.
+        let arc: Arc<[i32]> = Arc::from(vec![4, 2]);
+        assert_eq!(format!("{arc:?}"), "[4, 2]");
+        assert_eq!(format!("{arc:p}"), format!("{:p}", Arc::as_ptr(&arc)));
+    }
+
+    #[test]
+    fn slice_from_array_test() {
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        let arc: Arc<[DetectDrop<&str>]> =
+            [DetectDrop(&NUM_DROPS, "4"), DetectDrop(&NUM_DROPS, "2")].into();
+        // Moved, not cloned: nothing was dropped on the way.
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 0);
+        assert_eq!(arc.len(), 2);
+        assert_eq!((arc[0].1, arc[1].1), ("4", "2"));
+        drop(arc);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 2);
+
+        let empty: Arc<[DetectDrop<&str>]> = Arc::from([]);
+        assert!(empty.is_empty());
+        let units: Arc<[()]> = [(); 3].into();
+        assert_eq!(units.len(), 3);
+
+        // Boxes move without being reallocated, so the `Arc` ends up owning the very same ones.
+        let boxes = [Box::new(4), Box::new(2)];
+        let addresses = boxes.each_ref().map(|b| &**b as *const i32);
+        let arc: Arc<[Box<i32>]> = boxes.into();
+        assert!(arc.iter().map(|b| &**b as *const i32).eq(addresses));
+        assert_eq!((*arc[0], *arc[1]), (4, 2));
+    }
+
+    #[test]
+    fn array_from_slice_test() {
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        let slice: Arc<[DetectDrop<u8>]> =
+            Arc::from([DetectDrop(&NUM_DROPS, 4), DetectDrop(&NUM_DROPS, 2)]);
+        let clone = slice.clone();
+
+        // A different length gives the same `Arc<[T]>` back, with the count unchanged.
+        let slice = Arc::<[DetectDrop<u8>; 3]>::try_from(slice).err().unwrap();
+        let slice = Arc::<[DetectDrop<u8>; 0]>::try_from(slice).err().unwrap();
+        assert!(Arc::ptr_eq(&slice, &clone));
+        assert_eq!(Arc::strong_count(&clone), 2);
+
+        // The right length keeps the same allocation, and takes over the strong reference.
+        let weak = Arc::downgrade(&clone);
+        let array = Arc::<[DetectDrop<u8>; 2]>::try_from(slice).ok().unwrap();
+        assert!(std::ptr::addr_eq(Arc::as_ptr(&array), Arc::as_ptr(&clone)));
+        assert_eq!(Arc::strong_count(&array), 2);
+        assert_eq!((array[0].1, array[1].1), (4, 2));
+        drop(clone);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 0);
+        // The data is dropped (and the allocation freed) through the array type.
+        drop(array);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 2);
+        assert!(weak.upgrade().is_none());
+        drop(weak);
+
+        let empty: Arc<[String]> = Arc::from(Vec::new());
+        let empty: Arc<[String; 0]> = empty.try_into().ok().unwrap();
+        assert_eq!(empty.len(), 0);
+    }
+
+    #[test]
+    fn new_uninit_slice_test() {
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        let before = allocations();
+        let mut arc = Arc::<[DetectDrop<usize>]>::new_uninit_slice(5);
+        assert_eq!(arc.len(), 5);
+        for (i, x) in Arc::get_mut(&mut arc).unwrap().iter_mut().enumerate() {
+            x.write(DetectDrop(&NUM_DROPS, i));
+        }
+        let ptr = Arc::as_ptr(&arc).cast::<DetectDrop<usize>>();
+        let arc = unsafe { arc.assume_init() };
+        // Just the one allocation, which `assume_init` kept.
+        assert_eq!(allocations(), before + 1);
+        assert_eq!(arc.as_ptr(), ptr);
+        assert!(arc.iter().enumerate().all(|(i, x)| x.1 == i));
+        let weak = Arc::downgrade(&arc);
+        drop(arc);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 5);
+        assert!(weak.upgrade().is_none());
+        drop(weak);
+
+        // Dropped before `assume_init`, the elements aren't (they might never have been written), only the
+        // allocation is freed.
+        let mut arc = Arc::<[DetectDrop<usize>]>::new_uninit_slice(2);
+        Arc::get_mut(&mut arc).unwrap()[0].write(DetectDrop(&NUM_DROPS, 0));
+        drop(arc);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 5);
+
+        let zeroed = unsafe { Arc::<[u128]>::new_zeroed_slice(100).assume_init() };
+        assert!(zeroed.iter().all(|&x| x == 0));
+        let empty = unsafe { Arc::<[String]>::new_uninit_slice(0).assume_init() };
+        assert!(empty.is_empty());
+        let units = unsafe { Arc::<[()]>::new_uninit_slice(0).assume_init() };
+        assert!(units.is_empty());
+        let units = unsafe { Arc::<[()]>::new_zeroed_slice(usize::MAX).assume_init() };
+        assert_eq!(units.len(), usize::MAX);
+
+        // Too big to lay out, whether the elements alone are or only together with the header.
+        for len in [usize::MAX, isize::MAX as usize / 8] {
+            let result = std::panic::catch_unwind(|| Arc::<[u64]>::new_uninit_slice(len));
+            assert!(result.is_err());
+        }
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore = "allocates and fills 1 GiB")]
+    fn huge_uninit_slice_test() {
+        const LEN: usize = 1 << 30;
+        let mut arc = Arc::<[u8]>::new_uninit_slice(LEN);
+        // A single `memset`, so this is fast even without optimizations (unlike a loop over the elements).
+        let elements = Arc::get_mut(&mut arc).unwrap();
+        unsafe { elements.as_mut_ptr().write_bytes(7, LEN) };
+        let arc = unsafe { arc.assume_init() };
+        let frozen = arc.clone();
+        assert_eq!(frozen.len(), LEN);
+        assert!(frozen.iter().step_by(4096).all(|&b| b == 7));
+        assert_eq!((frozen[0], frozen[LEN - 1]), (7, 7));
+    }
+
+    #[test]
+    fn slice_from_slice_test() {
+        static NUM_CLONES: AtomicUsize = AtomicUsize::new(0);
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        // Panics when cloned with `panics` set, so cloning a slice of them can fail halfway through.
+        struct Detect {
+            n: u8,
+            panics: bool,
+            _detect: DetectDrop<'static>,
+        }
+
+        impl Clone for Detect {
+            fn clone(&self) -> Self {
+                if self.panics {
+                    // Skips the panic hook, which would allocate (and cache) a backtrace and throw off the counts.
+                    std::panic::resume_unwind(Box::new("can't clone this one"));
+                }
+                NUM_CLONES.fetch_add(1, Ordering::Relaxed);
+                Detect {
+                    n: self.n,
+                    panics: false,
+                    _detect: DetectDrop::new(&NUM_DROPS),
+                }
+            }
+        }
+
+        let originals: Vec<_> = (0..10)
+            .map(|n| Detect {
+                n,
+                panics: false,
+                _detect: DetectDrop::new(&NUM_DROPS),
+            })
+            .collect();
+        let arc: Arc<[Detect]> = Arc::from(&originals[..]);
+        assert_eq!(NUM_CLONES.load(Ordering::Relaxed), 10);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 0);
+        assert_eq!(arc.len(), 10);
+        assert!(arc.iter().enumerate().all(|(i, x)| x.n == i as u8));
+        drop(arc);
+        // Only the clones are dropped, each exactly once.
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 10);
+
+        let empty: Arc<[Detect]> = Arc::from(&originals[..0]);
+        assert!(empty.is_empty());
+        drop(empty);
+        assert_eq!(NUM_CLONES.load(Ordering::Relaxed), 10);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 10);
+
+        // Panics while cloning the fourth element, after three clones were already written into the allocation.
+        let mut originals = originals;
+        originals[3].panics = true;
+        // The first unwind on a thread caches a few things, so get that out of the way before counting.
+        drop(std::panic::catch_unwind(|| {
+            std::panic::resume_unwind(Box::new(()))
+        }));
+        let before = (allocations(), deallocations());
+        let result = std::panic::catch_unwind(|| Arc::<[Detect]>::from(&originals[..]));
+        assert!(result.is_err());
+        drop(result);
+        // Everything allocated on the way (the `Arc`'s block, and the panic's payload) is freed again.
+        assert!(allocations() > before.0);
+        assert_eq!(allocations() - before.0, deallocations() - before.1);
+        assert_eq!(NUM_CLONES.load(Ordering::Relaxed), 13);
+        // The three clones that made it are dropped exactly once, and the originals not at all.
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 13);
+        drop(originals);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 23);
+    }
+
+    #[test]
+    fn empty_and_zst_slice_test() {
+        let empty: Arc<[String]> = Arc::from(Vec::new());
+        assert!(empty.is_empty());
+        assert_eq!(Arc::strong_count(&empty.clone()), 2);
+
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        // Zero-sized, unlike the shared `DetectDrop`.
+        struct ZeroSizedDetectDrop;
+
+        impl Drop for ZeroSizedDetectDrop {
+            fn drop(&mut self) {
+                NUM_DROPS.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        // Zero-sized elements take no space, but are still counted and dropped.
+        let zsts: Arc<[ZeroSizedDetectDrop]> =
+            Arc::from((0..5).map(|_| ZeroSizedDetectDrop).collect::<Vec<_>>());
+        assert_eq!(zsts.len(), 5);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 0);
+        drop(zsts);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 5);
+
+        let units: Arc<[()]> = Arc::from(vec![(); 1000]);
+        assert_eq!(units.len(), 1000);
+    }
+
+    #[test]
+    fn new_zeroed_test() {
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        // Not the shared `DetectDrop`, whose counter reference can't be all zeroes: this one has no bytes at all, so
+        // all zeroes is a valid `Zeroable`.
+        struct ZeroSizedDetectDrop;
+
+        impl Drop for ZeroSizedDetectDrop {
+            fn drop(&mut self) {
+                NUM_DROPS.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        struct Zeroable {
+            bytes: [u8; 1 << 20],
+            _detect: ZeroSizedDetectDrop,
+        }
+
+        let arc = Arc::<Zeroable>::new_zeroed();
+        assert_eq!(Arc::strong_count(&arc), 1);
+        assert_eq!(Arc::weak_count(&arc), 0);
+        let arc = unsafe { arc.assume_init() };
+        assert!(arc.bytes.iter().all(|&b| b == 0));
+
+        let clone = arc.clone();
+        let weak = Arc::downgrade(&arc);
+        assert_eq!((Arc::strong_count(&arc), Arc::weak_count(&arc)), (2, 1));
+        drop(arc);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 0);
+        drop(clone);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 1);
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[test]
+    fn new_zeroed_reused_memory_test() {
+        for _ in 0..10 {
+            // Leave a block of the same size full of non-zero bytes behind, which the allocator will likely reuse.
+            drop(Arc::new([0xFFu8; 1024]));
+            let arc = unsafe { Arc::<[u8; 1024]>::new_zeroed().assume_init() };
+            assert_eq!(*arc, [0; 1024]);
+            // The counts were set up properly despite the zeroing.
+            assert_eq!((Arc::strong_count(&arc), Arc::weak_count(&arc)), (1, 0));
+            let weak = Arc::downgrade(&arc);
+            drop(arc);
+            assert!(weak.upgrade().is_none());
+        }
+    }
+
+    #[test]
+    fn str_test() {
+        use std::collections::HashSet;
+
+        let literal: Arc<str> = Arc::from("héllo, wörld");
+        let owned: Arc<str> = Arc::from(String::from("héllo, wörld"));
+        assert_eq!(literal.len(), "héllo, wörld".len());
+        assert!(literal == owned);
+        assert!(!Arc::ptr_eq(&literal, &owned));
+
+        let empty: Arc<str> = Arc::from("");
+        assert!(empty.is_empty());
+
+        // `Arc<str>` hashes and compares like `str`, so a set of them can be queried with a `&str`.
+        let mut set = HashSet::new();
+        set.insert(literal.clone());
+        assert!(set.contains("héllo, wörld"));
+        assert!(!set.insert(owned));
+        assert_eq!(Arc::strong_count(&literal), 2);
+    }
+
+    #[test]
+    fn c_str_test() {
+        use std::ffi::{CStr, CString};
+
+        // Any bytes but nul, UTF-8 or not.
+        let original = c"\xff\xfeh\xc3\xa9llo";
+        let borrowed: Arc<CStr> = Arc::from(original);
+        let owned: Arc<CStr> = Arc::from(CString::from(original));
+        for arc in [borrowed, owned] {
+            assert_eq!(&*arc, original);
+            assert_eq!(arc.to_bytes_with_nul(), original.to_bytes_with_nul());
+            assert_eq!(CString::from(&*arc), CString::from(original));
+            let clone = arc.clone();
+            drop(arc);
+            assert_eq!(&*clone, original);
+        }
+
+        // Just the nul terminator.
+        let empty: Arc<CStr> = Arc::from(c"");
+        assert!(empty.is_empty());
+        assert_eq!(empty.to_bytes_with_nul(), b"\0");
+        let empty: Arc<CStr> = Arc::from(CString::default());
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn os_str_and_path_test() {
+        use std::ffi::{OsStr, OsString};
+        use std::path::{Path, PathBuf};
+
+        let check = |original: &OsStr| {
+            let borrowed: Arc<OsStr> = Arc::from(original);
+            let owned: Arc<OsStr> = Arc::from(original.to_os_string());
+            assert_eq!(&*borrowed, original);
+            assert_eq!(&*owned, original);
+            assert_eq!(borrowed.as_encoded_bytes(), original.as_encoded_bytes());
+
+            let path = Path::new(original);
+            let borrowed: Arc<Path> = Arc::from(path);
+            let owned: Arc<Path> = Arc::from(path.to_path_buf());
+            assert_eq!(&*borrowed, path);
+            assert_eq!(&*owned, path);
+            assert_eq!(borrowed.components().count(), path.components().count());
+            assert_eq!(PathBuf::from(owned.as_os_str()), path);
+        };
+
+        check(OsStr::new(""));
+        check(OsStr::new("/tmp/héllo/wörld.txt"));
+
+        // Not valid Unicode, so only representable as an `OsStr`.
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::{OsStrExt, OsStringExt};
+            check(OsStr::from_bytes(b"/tmp/\xff\xfe/file"));
+            let arc: Arc<OsStr> = Arc::from(OsString::from_vec(b"\x80abc".to_vec()));
+            assert_eq!(arc.as_bytes(), b"\x80abc");
+            assert!(arc.to_str().is_none());
+        }
+        #[cfg(windows)]
+        {
+            use std::os::windows::ffi::{OsStrExt, OsStringExt};
+            // A lone surrogate, which no `str` can hold, and a character outside the basic plane.
+            let wide = [0x43, 0x3a, 0x5c, 0xd800, 0x5c, 0xd83e, 0xdd80];
+            let original = OsString::from_wide(&wide);
+            check(&original);
+            let arc: Arc<OsStr> = Arc::from(original);
+            assert_eq!(arc.encode_wide().collect::<Vec<_>>(), wide);
+            assert!(arc.to_str().is_none());
+        }
+    }
+
+    #[test]
+    fn str_from_iter_test() {
+        let chars: Arc<str> = "héllo, wörld 🦀".chars().collect();
+        assert_eq!(&*chars, "héllo, wörld 🦀");
+
+        let parts = ["日本", "", "語", "🦀"];
+        let borrowed: Arc<str> = parts.iter().copied().collect();
+        let owned: Arc<str> = parts.iter().map(|s| s.to_string()).collect();
+        assert_eq!(&*borrowed, "日本語🦀");
+        assert!(borrowed == owned);
+
+        // Empty iterators (and ones only yielding empty strings) give a valid empty `Arc<str>`.
+        let empty: Arc<str> = std::iter::empty::<char>().collect();
+        assert!(empty.is_empty());
+        let empty: Arc<str> = std::iter::empty::<&str>().collect();
+        assert!(empty.is_empty());
+        let empty: Arc<str> = ["", ""].into_iter().map(String::from).collect();
+        assert_eq!(&*empty, "");
+        assert_eq!(Arc::strong_count(&empty.clone()), 2);
+    }
+
+    #[test]
+    fn str_from_test() {
+        for original in ["", "hello", "héllo, wörld", "日本語", "🦀🦀"] {
+            let borrowed: Arc<str> = Arc::from(original);
+            let owned: Arc<str> = Arc::from(String::from(original));
+            for arc in [borrowed, owned] {
+                assert_eq!(&*arc, original);
+                assert_eq!(arc.len(), original.len());
+                assert_eq!(arc.chars().count(), original.chars().count());
+
+                let clone = arc.clone();
+                assert!(Arc::ptr_eq(&arc, &clone));
+                assert_eq!(&*clone, original);
+                assert_eq!(Arc::strong_count(&arc), 2);
+                drop(arc);
+                assert_eq!(&*clone, original);
+                assert_eq!(Arc::strong_count(&clone), 1);
+
+                let weak = Arc::downgrade(&clone);
+                drop(clone);
+                assert!(weak.upgrade().is_none());
+            }
+        }
+    }
+
+    #[test]
+    fn cow_test() {
+        use std::borrow::Cow;
+
+        static NUM_CLONES: AtomicUsize = AtomicUsize::new(0);
+
+        struct Detect;
+
+        impl Clone for Detect {
+            fn clone(&self) -> Self {
+                NUM_CLONES.fetch_add(1, Ordering::Relaxed);
+                Self
+            }
+        }
+
+        // Borrowed elements are cloned, owned ones moved.
+        let elements = [Detect, Detect];
+        let arc: Arc<[Detect]> = Cow::Borrowed(&elements[..]).into();
+        assert_eq!(arc.len(), 2);
+        assert_eq!(NUM_CLONES.load(Ordering::Relaxed), 2);
+        let arc: Arc<[Detect]> = Cow::<[Detect]>::Owned(Vec::from(elements)).into();
+        assert_eq!(arc.len(), 2);
+        assert_eq!(NUM_CLONES.load(Ordering::Relaxed), 2);
+
+        // An owned string's bytes are copied once, into the new allocation, and its buffer freed.
+        let owned: Cow<str> = Cow::Owned(String::from("héllo"));
+        let before = (allocations(), deallocations());
+        let arc: Arc<str> = owned.into();
+        assert_eq!(allocations(), before.0 + 1);
+        assert_eq!(deallocations(), before.1 + 1);
+        assert_eq!(&*arc, "héllo");
+        let arc: Arc<str> = Cow::Borrowed("héllo").into();
+        assert_eq!(&*arc, "héllo");
+
+        for cow in [Cow::Borrowed(""), Cow::Owned(String::new())] {
+            let arc: Arc<str> = cow.into();
+            assert_eq!(&*arc, "");
+        }
+        for cow in [Cow::Borrowed(&[][..]), Cow::Owned(Vec::new())] {
+            let arc: Arc<[String]> = cow.into();
+            assert!(arc.is_empty());
+        }
+    }
+
+    #[test]
+    fn try_new_uninit_test() {
+        arcdata::FAIL_ALLOCATIONS.set(true);
+        let uninit = Arc::<[u8; 64]>::try_new_uninit();
+        let zeroed = Arc::<[u8; 64]>::try_new_zeroed();
+        arcdata::FAIL_ALLOCATIONS.set(false);
+        assert!(matches!(uninit, Err(AllocError)));
+        assert!(matches!(zeroed, Err(AllocError)));
+
+        // `try_new` hands the data back instead, untouched.
+        arcdata::FAIL_ALLOCATIONS.set(true);
+        let result = Arc::try_new(vec![4, 2]);
+        arcdata::FAIL_ALLOCATIONS.set(false);
+        let error = result.unwrap_err();
+        assert_eq!(error.to_string(), AllocError.to_string());
+        assert_eq!(error.into_inner(), [4, 2]);
+
+        // The data is only moved in after allocating succeeded, so a failure leaves it with the caller.
+        let data = vec![4, 2];
+        let mut arc = Arc::<Vec<i32>>::try_new_uninit().unwrap();
+        Arc::get_mut(&mut arc).unwrap().write(data);
+        let arc = unsafe { arc.assume_init() };
+        assert_eq!(*arc, [4, 2]);
+
+        let arc = unsafe { Arc::<[u8; 64]>::try_new_zeroed().unwrap().assume_init() };
+        assert_eq!(*arc, [0; 64]);
+        assert_eq!(Arc::strong_count(&arc), 1);
+    }
+
+    #[test]
+    fn from_iter_test() {
+        // Exact size, written straight into place.
+        let exact: Arc<[String]> = vec![String::from("4"), String::from("2")]
+            .into_iter()
+            .collect();
+        assert_eq!(*exact, ["4", "2"]);
+        let range: Arc<[u64]> = (1..=100).collect();
+        assert_eq!(range.len(), 100);
+        assert_eq!(range.iter().sum::<u64>(), 5050);
+
+        // Unknown size, collected into a `Vec` first.
+        let filtered: Arc<[i32]> = (0..10).filter(|x| x % 3 == 0).collect();
+        assert_eq!(*filtered, [0, 3, 6, 9]);
+
+        let empty: Arc<[i32]> = std::iter::empty().collect();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn from_iter_lying_size_hint_test() {
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        // Claims to have exactly `claimed` items left, but really has `actual`.
+        struct Liar {
+            claimed: usize,
+            actual: usize,
+            next: usize,
+        }
+
+        impl Iterator for Liar {
+            type Item = DetectDrop<'static, usize>;
+
+            fn next(&mut self) -> Option<Self::Item> {
+                (self.next < self.actual).then(|| {
+                    self.next += 1;
+                    DetectDrop(&NUM_DROPS, self.next - 1)
+                })
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                let claimed = self.claimed.saturating_sub(self.next);
+                (claimed, Some(claimed))
+            }
+        }
+
+        let mut total = 0;
+        for (claimed, actual) in [(5, 2), (2, 5), (3, 0), (0, 3), (4, 4)] {
+            let arc: Arc<[DetectDrop<usize>]> = Liar {
+                claimed,
+                actual,
+                next: 0,
+            }
+            .collect();
+            // Every item there really was, in order, however many were promised.
+            assert_eq!(arc.len(), actual);
+            assert!(arc.iter().enumerate().all(|(i, x)| x.1 == i));
+            // Moving the items around when the size turns out to be wrong must not drop any of them.
+            assert_eq!(NUM_DROPS.load(Ordering::Relaxed), total);
+            drop(arc);
+            total += actual;
+            assert_eq!(NUM_DROPS.load(Ordering::Relaxed), total);
+        }
+    }
+
+    #[test]
+    fn from_iter_panic_test() {
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        // Panics while producing the fourth item, after three were already written into the allocation.
+        let result = std::panic::catch_unwind(|| {
+            (0..10)
+                .map(|i| {
+                    if i == 3 {
+                        panic!("no more items");
+                    }
+                    DetectDrop::new(&NUM_DROPS)
+                })
+                .collect::<Arc<[DetectDrop]>>()
+        });
+        assert!(result.is_err());
+        // The three items that made it are dropped exactly once.
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn is_unique_test() {
+        let arc = Arc::new(42);
+        assert!(Arc::is_unique(&arc));
+
+        let clone = arc.clone();
+        assert!(!Arc::is_unique(&arc));
+        assert!(!Arc::is_unique(&clone));
+        drop(clone);
+        assert!(Arc::is_unique(&arc));
+
+        // A `Weak` could be upgraded at any time, so it counts too.
+        let weak = Arc::downgrade(&arc);
+        assert!(!Arc::is_unique(&arc));
+        let upgraded = weak.upgrade().unwrap();
+        assert!(!Arc::is_unique(&upgraded));
+        drop(upgraded);
+        drop(weak);
+        assert!(Arc::is_unique(&arc));
+
+        // A clone dropped on another thread is seen as gone once the thread was joined.
+        let clone = arc.clone();
+        std::thread::spawn(move || drop(clone)).join().unwrap();
+        assert!(Arc::is_unique(&arc));
+        // Checking doesn't leave the weak count locked.
+        assert_eq!(Arc::weak_count(&arc), 0);
+        let _weak = Arc::downgrade(&arc);
+        assert_eq!(Arc::weak_count(&arc), 1);
+    }
+
+    #[test]
+    fn is_unique_vs_downgrade_test() {
+        let rounds = if cfg!(miri) { 50 } else { 10_000 };
+        let arc = Arc::new(42);
+        std::thread::scope(|s| {
+            // Downgrades through a shared reference while the other thread checks, so some of the downgrades
+            // run into the locked weak count.
+            s.spawn(|| {
+                for _ in 0..rounds {
+                    let weak = Arc::downgrade(&arc);
+                    assert_eq!(*weak.upgrade().unwrap(), 42);
+                }
+            });
+            s.spawn(|| {
+                for _ in 0..rounds {
+                    // The answer depends on whether a `Weak` exists right now. What matters is that the lock
+                    // doesn't lose any of the other thread's changes to the weak count.
+                    let _ = Arc::is_unique(&arc);
+                }
+            });
+        });
+        // No downgrade (or drop of a `Weak`) was lost to a check unlocking the weak count.
+        assert_eq!(Arc::weak_count(&arc), 0);
+        assert_eq!(Arc::strong_count(&arc), 1);
+        assert!(Arc::is_unique(&arc));
+
+        // Strong-shared and weak-shared, checked from another thread.
+        let clone = arc.clone();
+        std::thread::scope(|s| {
+            s.spawn(|| assert!(!Arc::is_unique(&arc)));
+        });
+        drop(clone);
+        let weak = Arc::downgrade(&arc);
+        std::thread::scope(|s| {
+            s.spawn(|| assert!(!Arc::is_unique(&arc)));
+        });
+        drop(weak);
+        assert!(Arc::is_unique(&arc));
+    }
+
+    #[test]
+    fn downcast_test() {
+        use std::any::{Any, TypeId};
+        use std::collections::HashMap;
+
+        let arc = Arc::new(String::from("hello"));
+        let any: Arc<dyn Any + Send + Sync> = Arc::from(arc.clone());
+        assert_eq!(Arc::strong_count(&arc), 2);
+
+        // A failed downcast gives the same `Arc` back, with the count unchanged.
+        let any = any.downcast::<i32>().unwrap_err();
+        assert_eq!(Arc::strong_count(&any), 2);
+
+        let string = any.downcast::<String>().ok().unwrap();
+        assert!(Arc::ptr_eq(&string, &arc));
+        assert_eq!(Arc::strong_count(&arc), 2);
+        drop(string);
+        assert_eq!(Arc::strong_count(&arc), 1);
+
+        // A registry keyed by type, where the key already says what the type is.
+        let mut registry: HashMap<TypeId, Arc<dyn Any + Send + Sync>> = HashMap::new();
+        registry.insert(TypeId::of::<String>(), Arc::from(arc.clone()));
+        registry.insert(TypeId::of::<i32>(), Arc::from(Arc::new(42)));
+        let any = registry[&TypeId::of::<String>()].clone();
+        let string = unsafe { any.downcast_unchecked::<String>() };
+        assert!(Arc::ptr_eq(&string, &arc));
+        assert_eq!(Arc::strong_count(&arc), 3);
+        let number = unsafe {
+            registry[&TypeId::of::<i32>()]
+                .clone()
+                .downcast_unchecked::<i32>()
+        };
+        assert_eq!(*number, 42);
+        drop((string, registry));
+        assert_eq!(Arc::strong_count(&arc), 1);
+
+        // The erased `Arc` drops the data like any other.
+        let weak = Arc::downgrade(&arc);
+        let any: Arc<dyn Any + Send + Sync> = Arc::from(arc);
+        drop(any);
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[test]
+    fn leak_test() {
+        let arc = Arc::new(vec![4, 2]);
+        let weak = Arc::downgrade(&arc);
+        let leaked: &'static Vec<i32> = Arc::leak(arc.clone());
+        drop(arc);
+
+        // The leaked reference outlives every `Arc`, even on another thread.
+        let sum = std::thread::spawn(move || leaked.iter().sum::<i32>())
+            .join()
+            .unwrap();
+        assert_eq!(sum, 6);
+        assert_eq!(weak.strong_count(), 1);
+        assert!(std::ptr::eq(leaked, &*weak.upgrade().unwrap()));
+        drop(weak);
+
+        // Kept in a static, like an interning table would, where it's still reachable. So Miri's leak check (which
+        // the allocation would otherwise fail) passes, while it still checks that nothing frees it.
+        static INTERNED: std::sync::atomic::AtomicPtr<Vec<i32>> =
+            std::sync::atomic::AtomicPtr::new(core::ptr::null_mut());
+        INTERNED.store(core::ptr::from_ref(leaked).cast_mut(), Ordering::Relaxed);
+        let interned = unsafe { &*INTERNED.load(Ordering::Relaxed) };
+        assert_eq!(*interned, [4, 2]);
+    }
+
+    #[test]
+    #[cfg(feature = "nightly")]
+    fn coerce_unsized_test() {
+        use std::any::Any;
+        use std::fmt::Display;
+
+        let any: Arc<dyn Any + Send + Sync> = Arc::new(42);
+        assert_eq!(*any.downcast::<i32>().ok().unwrap(), 42);
+
+        let array = Arc::new([4, 2]);
+        let slice: Arc<[i32]> = array.clone();
+        assert_eq!(*slice, [4, 2]);
+        assert_eq!(Arc::strong_count(&array), 2);
+
+        // Trait methods go through the vtable.
+        let display: Arc<dyn Display> = Arc::new(42);
+        assert_eq!(display.to_string(), "42");
+        let weak: Weak<dyn Display> = Arc::downgrade(&display);
+        assert_eq!(weak.upgrade().unwrap().to_string(), "42");
+    }
+
+    #[test]
+    #[cfg(feature = "nightly")]
+    fn arc_self_receiver_test() {
+        trait Shape {
+            fn area(&self) -> u32;
+            // Takes the `Arc` itself, so it can keep (or give away) the reference.
+            fn keep(self: Arc<Self>) -> Arc<dyn Shape>;
+        }
+
+        struct Square(u32);
+
+        impl Shape for Square {
+            fn area(&self) -> u32 {
+                self.0 * self.0
+            }
+
+            fn keep(self: Arc<Self>) -> Arc<dyn Shape> {
+                self
+            }
+        }
+
+        let square = Arc::new(Square(3));
+        let shape: Arc<dyn Shape> = square.clone();
+        // Called through the vtable, with the `Arc<dyn Shape>` turned back into an `Arc<Square>` for `keep`.
+        let kept = shape.keep();
+        assert_eq!(kept.area(), 9);
+        assert!(std::ptr::addr_eq(Arc::as_ptr(&kept), Arc::as_ptr(&square)));
+        assert_eq!(Arc::strong_count(&square), 2);
+        drop(kept);
+        assert_eq!(Arc::strong_count(&square), 1);
+    }
+
+    #[test]
+    fn new_with_test() {
+        use std::mem::MaybeUninit;
+
+        // 16 MiB, far more than the stack of a test thread: any copy through the stack would overflow it.
+        struct Big {
+            id: u32,
+            table: [u8; 16 << 20],
+        }
+
+        let mut slot_addr = 0;
+        let big = Arc::new_with(|slot: &mut MaybeUninit<Big>| {
+            slot_addr = slot.as_ptr() as usize;
+            let ptr = slot.as_mut_ptr();
+            unsafe {
+                (&raw mut (*ptr).id).write(42);
+                (&raw mut (*ptr).table)
+                    .cast::<u8>()
+                    .write_bytes(7, 16 << 20);
+                slot.assume_init_mut()
+            }
+        });
+        // The data was built right where it ended up.
+        assert_eq!(Arc::as_ptr(&big) as usize, slot_addr);
+        assert_eq!(big.id, 42);
+        assert!(big.table.iter().all(|&b| b == 7));
+
+        // A panicking closure frees the allocation without dropping the uninitialized data.
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        let result = std::panic::catch_unwind(|| {
+            Arc::<DetectDrop>::new_with(|_| panic!("can't build it"));
+        });
+        assert!(result.is_err());
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 0);
+
+        // Returning some other `&mut T` is caught, since the slot would still be uninitialized.
+        let result = std::panic::catch_unwind(|| {
+            Arc::<DetectDrop>::new_with(|_| Box::leak(Box::new(DetectDrop::new(&NUM_DROPS))));
+        });
+        assert!(result.is_err());
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn finalizer_test() {
+        use std::sync::Mutex;
+
+        static EVENTS: Mutex<Vec<&str>> = Mutex::new(Vec::new());
+
+        struct Resource;
+
+        impl Drop for Resource {
+            fn drop(&mut self) {
+                EVENTS.lock().unwrap().push("drop");
+            }
+        }
+
+        // The finalizer runs right before `T`'s destructor, once the last `Arc` is gone.
+        let arc = Arc::new_with_finalizer(Resource, |_| EVENTS.lock().unwrap().push("finalize"));
+        let weak = Arc::downgrade(&arc);
+        let clone = arc.clone();
+        drop(arc);
+        assert!(EVENTS.lock().unwrap().is_empty());
+        drop(clone);
+        assert_eq!(*EVENTS.lock().unwrap(), ["finalize", "drop"]);
+        assert!(weak.upgrade().is_none());
+        EVENTS.lock().unwrap().clear();
+
+        // A panicking finalizer still lets the data be dropped.
+        let arc = Arc::new_with_finalizer(Resource, |_| panic!("finalizer failed"));
+        let weak = Arc::downgrade(&arc);
+        assert!(std::panic::catch_unwind(move || drop(arc)).is_err());
+        assert_eq!(*EVENTS.lock().unwrap(), ["drop"]);
+        assert_eq!(weak.strong_count(), 0);
+        EVENTS.lock().unwrap().clear();
+
+        // Moving the data out skips the finalizer.
+        let arc = Arc::new_with_finalizer(Resource, |_| EVENTS.lock().unwrap().push("finalize"));
+        let resource = Arc::into_inner(arc).unwrap();
+        drop(resource);
+        assert_eq!(*EVENTS.lock().unwrap(), ["drop"]);
+        EVENTS.lock().unwrap().clear();
+
+        // `make_mut` takes the finalizer along when it moves the data to a new allocation.
+        let mut arc = Arc::new_with_finalizer(5, |x| {
+            assert_eq!(*x, 6);
+            EVENTS.lock().unwrap().push("finalize")
+        });
+        let weak = Arc::downgrade(&arc);
+        *Arc::make_mut(&mut arc) += 1;
+        assert!(weak.upgrade().is_none());
+        assert!(EVENTS.lock().unwrap().is_empty());
+        drop(arc);
+        assert_eq!(*EVENTS.lock().unwrap(), ["finalize"]);
+    }
+
+    #[test]
+    fn finalizer_race_test() {
+        use std::any::Any;
+
+        static NUM_FINALIZED: AtomicUsize = AtomicUsize::new(0);
+
+        for i in 0..100 {
+            let arc = Arc::new_with_finalizer(i, move |x| {
+                assert_eq!(*x, i);
+                NUM_FINALIZED.fetch_add(1, Ordering::Relaxed);
+            });
+            // The finalizer keeps working after the type was erased.
+            let arc: Arc<dyn Any + Send + Sync> = Arc::from(arc);
+            let threads: Vec<_> = (0..4)
+                .map(|_| {
+                    let clone = arc.clone();
+                    std::thread::spawn(move || drop(clone))
+                })
+                .collect();
+            drop(arc);
+            for t in threads {
+                t.join().unwrap();
+            }
+            // Exactly one of the racing drops ran the finalizer.
+            assert_eq!(NUM_FINALIZED.load(Ordering::Relaxed), i + 1);
+        }
+    }
+
+    #[test]
+    fn clone_from_test() {
+        let arc = Arc::new(String::from("hello"));
+        let mut clone = arc.clone();
+        clone.clone_from(&arc);
+        assert_eq!(Arc::strong_count(&arc), 2);
+        clone.clone_from(&clone.clone());
+        assert_eq!(Arc::strong_count(&arc), 2);
+
+        // Onto another allocation, which is given back.
+        let mut other = Arc::new(String::from("world"));
+        let weak = Arc::downgrade(&other);
+        other.clone_from(&arc);
+        assert!(Arc::ptr_eq(&other, &arc));
+        assert_eq!(Arc::strong_count(&arc), 3);
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[test]
+    fn clones_test() {
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        let arc = Arc::new(DetectDrop::new(&NUM_DROPS));
+        let mut clones = Arc::clones(&arc, 64);
+        // All 64 are accounted for right away, even before any was handed out.
+        assert_eq!(Arc::strong_count(&arc), 65);
+        assert_eq!(clones.len(), 64);
+
+        let taken: Vec<_> = clones.by_ref().take(10).collect();
+        assert_eq!(clones.len(), 54);
+        assert!(taken.iter().all(|clone| Arc::ptr_eq(clone, &arc)));
+
+        // Dropping the iterator early gives back the 54 it still held.
+        drop(clones);
+        assert_eq!(Arc::strong_count(&arc), 11);
+        drop(taken);
+        assert_eq!(Arc::strong_count(&arc), 1);
+
+        // The iterator's references can be the last ones, in which case dropping it drops the data.
+        let clones = Arc::clones(&arc, 3);
+        drop(arc);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 0);
+        drop(clones);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 1);
+
+        // Fanning out to threads.
+        let arc = Arc::new(42);
+        let threads: Vec<_> = Arc::clones(&arc, 8)
+            .map(|clone| std::thread::spawn(move || *clone))
+            .collect();
+        for t in threads {
+            assert_eq!(t.join().unwrap(), 42);
+        }
+        assert_eq!(Arc::strong_count(&arc), 1);
+        assert_eq!(Arc::clones(&arc, 0).count(), 0);
+        assert_eq!(Arc::strong_count(&arc), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn into_inner_blocking_test() {
+        use std::time::Duration;
+
+        // The clone is only dropped after the main thread has (most likely) started waiting.
+        let arc = Arc::new(String::from("hello"));
+        let clone = arc.clone();
+        let t = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            drop(clone);
+        });
+        assert_eq!(Arc::into_inner_blocking(arc, None).unwrap(), "hello");
+        t.join().unwrap();
+
+        // Timing out gives the `Arc<T>` back untouched.
+        let arc = Arc::new(42);
+        let clone = arc.clone();
+        let arc = Arc::into_inner_blocking(arc, Some(Duration::from_millis(20))).unwrap_err();
+        assert_eq!(Arc::strong_count(&arc), 2);
+        assert_eq!(*arc, 42);
+        drop(clone);
+        // Nothing to wait for anymore, so even a zero timeout works.
+        assert_eq!(
+            Arc::into_inner_blocking(arc, Some(Duration::ZERO)).ok(),
+            Some(42)
+        );
+
+        // `Weak<T>`s don't count, but an `Arc<T>` upgraded from one does until it's dropped.
+        let arc = Arc::new(7);
+        let weak = Arc::downgrade(&arc);
+        let t = std::thread::spawn(move || {
+            let upgraded = weak.upgrade().unwrap();
+            std::thread::sleep(Duration::from_millis(50));
+            drop(upgraded);
+            weak
+        });
+        std::thread::sleep(Duration::from_millis(10));
+        assert_eq!(Arc::into_inner_blocking(arc, None).ok(), Some(7));
+        assert!(t.join().unwrap().upgrade().is_none());
+
+        // Many clones dropped concurrently, only the last of which wakes up the waiter.
+        for _ in 0..20 {
+            let arc = Arc::new(0);
+            let threads: Vec<_> = Arc::clones(&arc, 8)
+                .map(|clone| std::thread::spawn(move || drop(clone)))
+                .collect();
+            assert_eq!(Arc::into_inner_blocking(arc, None).ok(), Some(0));
+            for t in threads {
+                t.join().unwrap();
+            }
+        }
+
+        // Only one thread can wait at a time: whichever comes second gives up right away, which drops its
+        // `Arc<T>` and so lets the first one through.
+        let arc = Arc::new(1);
+        let clone = arc.clone();
+        let t = std::thread::spawn(move || Arc::into_inner_blocking(clone, None).ok());
+        std::thread::sleep(Duration::from_millis(20));
+        let here = Arc::into_inner_blocking(arc, None).ok();
+        let there = t.join().unwrap();
+        assert_eq!(here.or(there), Some(1));
+        assert!(here.is_none() || there.is_none());
+
+        // Counts and clones still work while a thread waits.
+        let arc = Arc::new(5);
+        let clone = arc.clone();
+        let t = std::thread::spawn(move || Arc::into_inner_blocking(arc, None).ok());
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(Arc::strong_count(&clone), 2);
+        let clones: Vec<_> = (0..4).map(|_| clone.clone()).collect();
+        assert_eq!(Arc::strong_count(&clone), 6);
+        drop(clones);
+        drop(clone);
+        assert_eq!(t.join().unwrap(), Some(5));
+    }
+
+    #[test]
+    fn map_test() {
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        // Unique and the same layout: the allocation is reused.
+        let arc = Arc::new(DetectDrop(&NUM_DROPS, 21u64));
+        let ptr = Arc::as_ptr(&arc).cast::<u8>();
+        let mapped = Arc::map(arc, |data| DetectDrop(data.0, data.1 as i64 * 2));
+        assert_eq!(mapped.1, 42);
+        assert_eq!(Arc::as_ptr(&mapped).cast::<u8>(), ptr);
+        assert_eq!(Arc::strong_count(&mapped), 1);
+        assert_eq!(Arc::weak_count(&mapped), 0);
+        // The old data was moved into `f`, which dropped it.
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 1);
+        // The result is a perfectly normal `Arc<U>`.
+        let weak = Arc::downgrade(&mapped);
+        let clone = mapped.clone();
+        drop(mapped);
+        assert_eq!(weak.upgrade().map(|arc| arc.1), Some(42));
+        drop(clone);
+        assert!(weak.upgrade().is_none());
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 2);
+
+        // Shared: `f` gets a clone, and the other `Arc<T>`s keep the original.
+        let arc = Arc::new(DetectDrop(&NUM_DROPS, 1));
+        let clone = arc.clone();
+        let mapped = Arc::map(arc, |data| data.1 + 1);
+        assert_eq!(*mapped, 2);
+        assert_eq!(clone.1, 1);
+        assert_eq!(Arc::strong_count(&clone), 1);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 3);
+
+        // A `Weak<T>` is enough to prevent the reuse, or it could upgrade to the wrong type later. The data is
+        // still moved out rather than cloned, though.
+        let weak = Arc::downgrade(&clone);
+        let mapped = Arc::map(clone, |data| data.1 + 2);
+        assert_eq!(*mapped, 3);
+        assert!(weak.upgrade().is_none());
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 4);
+
+        // Different layouts can't reuse the allocation, but still work.
+        let arc = Arc::new(7u8);
+        let mapped = Arc::map(arc, |n| [u64::from(n); 4]);
+        assert_eq!(*mapped, [7; 4]);
+        let mapped = Arc::map(mapped, |array| array.iter().sum::<u64>() as u8);
+        assert_eq!(*mapped, 28);
+
+        // A panic in `f` drops the data it was given once, and frees the allocation (which Miri would catch).
+        let arc = Arc::new(DetectDrop(&NUM_DROPS, 0));
+        let result = std::panic::catch_unwind(move || {
+            Arc::map(arc, |data| -> u64 {
+                let _data = data;
+                panic!("mapping failed")
+            })
+        });
+        assert!(result.is_err());
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 5);
+
+        // Moving the data out means the finalizer is dropped, not called.
+        let finalized = std::sync::Arc::new(AtomicUsize::new(0));
+        let arc = Arc::new_with_finalizer(DetectDrop(&NUM_DROPS, 3), {
+            let finalized = finalized.clone();
+            move |_| {
+                finalized.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+        let mapped = Arc::map(arc, |data| data.1);
+        drop(mapped);
+        assert_eq!(finalized.load(Ordering::Relaxed), 0);
+        assert_eq!(std::sync::Arc::strong_count(&finalized), 1);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 6);
+    }
+
+    #[test]
+    fn replace_and_take_test() {
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        // `Arc::take` needs a `Default`, which counts its drops like the rest.
+        struct Value {
+            n: u32,
+            _detect: DetectDrop<'static>,
+        }
+
+        fn value(n: u32) -> Value {
+            Value {
+                n,
+                _detect: DetectDrop::new(&NUM_DROPS),
+            }
+        }
+
+        impl Default for Value {
+            fn default() -> Self {
+                value(0)
+            }
+        }
+
+        // Unique: the old data is handed back, not dropped.
+        let mut arc = Arc::new(value(1));
+        let old = Arc::replace(&mut arc, value(2)).unwrap();
+        assert_eq!(old.n, 1);
+        assert_eq!(arc.n, 2);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 0);
+        drop(old);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 1);
+
+        let taken = Arc::take(&mut arc).unwrap();
+        assert_eq!(taken.n, 2);
+        assert_eq!(arc.n, 0);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 1);
+        drop(taken);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 2);
+
+        // Shared: nothing changes, except that the rejected value is dropped.
+        let clone = arc.clone();
+        assert!(Arc::replace(&mut arc, value(3)).is_none());
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 3);
+        assert!(Arc::take(&mut arc).is_none());
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 3);
+        assert_eq!(clone.n, 0);
+        assert_eq!(Arc::strong_count(&arc), 2);
+        drop(clone);
+
+        // A `Weak<T>` counts as sharing too, since it could be upgraded to look at the data.
+        let weak = Arc::downgrade(&arc);
+        assert!(Arc::replace(&mut arc, value(4)).is_none());
+        assert!(Arc::take(&mut arc).is_none());
+        assert_eq!(weak.upgrade().unwrap().n, 0);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 4);
+        drop(weak);
+
+        // Once it's gone, both work again.
+        assert_eq!(Arc::replace(&mut arc, value(5)).map(|old| old.n), Some(0));
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 5);
+        drop(arc);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 6);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_test() {
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Config {
+            name: Arc<String>,
+            port: Arc<u32>,
+            tags: Arc<Vec<String>>,
+            limits: Option<Arc<Limits>>,
+        }
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Limits {
+            connections: u32,
+        }
+
+        // The `Arc`s are invisible in the output.
+        assert_eq!(
+            serde_json::to_string(&Arc::new(42u32)).unwrap(),
+            serde_json::to_string(&42u32).unwrap()
+        );
+
+        let shared_tags = Arc::new(vec![String::from("a"), String::from("b")]);
+        let config = Config {
+            name: Arc::new(String::from("server")),
+            port: Arc::new(8080),
+            tags: shared_tags.clone(),
+            limits: Some(Arc::new(Limits { connections: 64 })),
+        };
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(
+            json,
+            r#"{"name":"server","port":8080,"tags":["a","b"],"limits":{"connections":64}}"#
+        );
+
+        // Round-tripping gives equal data, in fresh (unshared) allocations.
+        let parsed: Config = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, config);
+        assert!(!Arc::ptr_eq(&parsed.tags, &shared_tags));
+        assert_eq!(Arc::strong_count(&parsed.tags), 1);
+        assert_eq!(Arc::strong_count(&shared_tags), 2);
+
+        // Unsized data serializes too.
+        let name: Arc<str> = Arc::from("server");
+        assert_eq!(serde_json::to_string(&name).unwrap(), r#""server""#);
+
+        // Errors come from `T`'s `Deserialize` as usual.
+        assert!(serde_json::from_str::<Arc<u32>>("-1").is_err());
+    }
+
+    #[test]
+    fn dyn_end_to_end_test() {
+        use std::any::Any;
+
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        // Everything below goes through the vtable: dropping the data, and freeing the allocation with the
+        // size and alignment of `DetectDrop` (which Miri checks).
+        let arc: Arc<dyn Any + Send + Sync> =
+            Arc::from(Arc::new(DetectDrop(&NUM_DROPS, [1u64, 2, 3])));
+        let weak = Arc::downgrade(&arc);
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let clone = arc.clone();
+                std::thread::spawn(move || {
+                    clone
+                        .downcast_ref::<DetectDrop<'static, [u64; 3]>>()
+                        .unwrap()
+                        .1
+                        .iter()
+                        .sum::<u64>()
+                })
+            })
+            .collect();
+        for t in threads {
+            assert_eq!(t.join().unwrap(), 6);
+        }
+        assert_eq!(Arc::strong_count(&arc), 1);
+        assert_eq!(Arc::weak_count(&arc), 1);
+        assert!(weak
+            .upgrade()
+            .unwrap()
+            .is::<DetectDrop<'static, [u64; 3]>>());
+
+        drop(arc);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 1);
+        assert!(weak.upgrade().is_none());
+        drop(weak);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "allocator_api")]
+    fn new_in_test() {
+        use std::alloc::{AllocError, Allocator, Global, Layout};
+        use std::ptr::NonNull;
+
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        // Forwards to `Global`, counting the allocations that are still live, or fails every allocation.
+        struct Counting {
+            live: AtomicUsize,
+            fail: bool,
+        }
+
+        unsafe impl Allocator for Counting {
+            fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+                if self.fail {
+                    return Err(AllocError);
+                }
+                let ptr = Global.allocate(layout)?;
+                self.live.fetch_add(1, Ordering::Relaxed);
+                Ok(ptr)
+            }
+
+            unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+                self.live.fetch_sub(1, Ordering::Relaxed);
+                unsafe { Global.deallocate(ptr, layout) }
+            }
+        }
+
+        let counting = Counting {
+            live: AtomicUsize::new(0),
+            fail: false,
+        };
+
+        // The memory is given back once the last `Arc` is gone.
+        let arc = Arc::new_in(DetectDrop::new(&NUM_DROPS), &counting);
+        assert!(std::ptr::eq(*Arc::allocator(&arc), &counting));
+        assert_eq!(counting.live.load(Ordering::Relaxed), 1);
+        let arc2 = arc.clone();
+        std::thread::scope(|s| {
+            for arc in (0..4).map(|_| arc.clone()) {
+                s.spawn(move || drop(arc));
+            }
+            drop(arc);
+        });
+        assert_eq!(counting.live.load(Ordering::Relaxed), 1);
+        drop(arc2);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 1);
+        assert_eq!(counting.live.load(Ordering::Relaxed), 0);
+
+        // Or once the last `Weak` is, if that outlives the `Arc`s.
+        let arc = Arc::new_in(DetectDrop::new(&NUM_DROPS), &counting);
+        let weak = Arc::downgrade(&arc);
+        let upgraded = weak.upgrade().unwrap();
+        assert!(Arc::ptr_eq(&arc, &upgraded));
+        drop((arc, upgraded));
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 2);
+        assert_eq!(counting.live.load(Ordering::Relaxed), 1);
+        assert!(weak.upgrade().is_none());
+        drop(weak);
+        assert_eq!(counting.live.load(Ordering::Relaxed), 0);
+
+        // A failed allocation hands the data back, and there's nothing to give back to the allocator.
+        let failing = Counting {
+            live: AtomicUsize::new(0),
+            fail: true,
+        };
+        let data = Arc::try_new_in(DetectDrop::new(&NUM_DROPS), &failing)
+            .err()
+            .unwrap()
+            .into_inner();
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 2);
+        drop(data);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 3);
+        assert_eq!(failing.live.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn atomic_arc_test() {
+        static NUM_CREATED: AtomicUsize = AtomicUsize::new(0);
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        // Each version holds its number twice, so a reader seeing a freed (or half-written) one would notice.
+        struct Version(usize, usize);
+
+        impl Version {
+            fn new(n: usize) -> Arc<Self> {
+                NUM_CREATED.fetch_add(1, Ordering::Relaxed);
+                Arc::new(Version(n, n))
+            }
+        }
+
+        impl Drop for Version {
+            fn drop(&mut self) {
+                assert_eq!(self.0, self.1);
+                self.1 = usize::MAX;
+                NUM_DROPS.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        // Far fewer rounds under miri, which makes up for it by trying many interleavings (with many seeds). There
+        // is no loom model of `AtomicArc`: that would take swapping every atomic in the crate for loom's, which is
+        // out of scope for now.
+        let rounds = if cfg!(miri) { 20 } else { 2000 };
+
+        let atomic = AtomicArc::new(Version::new(0));
+        std::thread::scope(|s| {
+            // Writers counting up together with `compare_exchange`, so readers should never see an older version
+            // after a newer one.
+            for _ in 0..4 {
+                s.spawn(|| {
+                    let mut last = 0;
+                    for _ in 0..rounds {
+                        let version = atomic.load();
+                        assert_eq!(version.0, version.1);
+                        assert!(version.0 >= last);
+                        last = version.0;
+                    }
+                });
+            }
+            for _ in 0..2 {
+                s.spawn(|| {
+                    for _ in 0..rounds {
+                        let mut current = atomic.load();
+                        loop {
+                            match atomic.compare_exchange(&current, Version::new(current.0 + 1)) {
+                                Ok(old) => {
+                                    assert!(Arc::ptr_eq(&old, &current));
+                                    break;
+                                }
+                                Err((actual, new)) => {
+                                    assert!(actual.0 > current.0);
+                                    current = actual;
+                                    drop(new);
+                                }
+                            }
+                        }
+                    }
+                });
+            }
+        });
+        assert_eq!(atomic.load().0, 2 * rounds);
+
+        // Writers replacing the version without looking, which is all the more likely to drop one a reader is
+        // just loading.
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for _ in 0..rounds {
+                        let version = atomic.load();
+                        assert_eq!(version.0, version.1);
+                    }
+                });
+            }
+            s.spawn(|| {
+                for n in 0..rounds {
+                    atomic.store(Version::new(n));
+                }
+            });
+            s.spawn(|| {
+                for n in 0..rounds {
+                    let old = atomic.swap(Version::new(n));
+                    assert_eq!(old.0, old.1);
+                }
+            });
+        });
+
+        // More readers than there can be loads in progress at once, while the same two versions are stored over
+        // and over, so that a load may take back another one's announcement (made before the pointer came back).
+        let (a, b) = (Version::new(1), Version::new(1));
+        atomic.store(b.clone());
+        std::thread::scope(|s| {
+            for _ in 0..16 {
+                s.spawn(|| {
+                    for _ in 0..rounds {
+                        let version = atomic.load();
+                        assert_eq!((version.0, version.1), (1, 1));
+                    }
+                });
+            }
+            s.spawn(|| {
+                for n in 0..rounds {
+                    atomic.store(if n % 2 == 0 { a.clone() } else { b.clone() });
+                }
+            });
+        });
+        atomic.store(a.clone());
+        // Every strong reference left for a load was dropped again, and the ones reserved for `b` given back.
+        assert_eq!(Arc::strong_count(&b), 1);
+        drop((a, b));
+
+        let last = atomic.into_inner();
+        assert_eq!(Arc::strong_count(&last), 1);
+        drop(last);
+        assert_eq!(
+            NUM_DROPS.load(Ordering::Relaxed),
+            NUM_CREATED.load(Ordering::Relaxed)
+        );
+    }
+
+    #[test]
+    fn atomic_arc_full_test() {
+        use std::time::Duration;
+
+        // Pretend that as many loads as can be in progress at once are, each stuck right after announcing itself
+        // and incrementing the strong count.
+        let first = Arc::new(1);
+        let atomic = AtomicArc::new(first.clone());
+        let max = align_of::<arcdata::ArcData<i32>>() - 1;
+        let ptr = atomic.ptr.load(Ordering::Relaxed);
+        atomic
+            .ptr
+            .store(ptr.map_addr(|addr| addr + max), Ordering::Relaxed);
+        let stuck: Vec<_> = (0..max).map(|_| first.clone()).collect();
+
+        std::thread::scope(|s| {
+            // Another load has to wait for one of them to finish...
+            let load = s.spawn(|| atomic.load());
+            std::thread::sleep(Duration::from_millis(20));
+            assert!(!load.is_finished());
+            // ...or for a writer, which doesn't wait for anyone: it leaves each of them a strong reference
+            // instead.
+            drop(atomic.swap(Arc::new(2)));
+            assert_eq!(*load.join().unwrap(), 2);
+        });
+        assert_eq!(Arc::strong_count(&first), 1 + 2 * max);
+
+        // Once the stuck loads go on, they find their announcements gone, and drop the references left for them.
+        for arc in stuck {
+            unsafe { Arc::decrement_strong_count(Arc::as_ptr(&arc)) };
+        }
+        assert_eq!(Arc::strong_count(&first), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "cache_padded")]
+    fn cache_padded_test() {
+        use core::mem::offset_of;
+
+        // The strong count gets a cache line all to itself, and the weak count starts the next one.
+        assert_eq!(offset_of!(arcdata::ArcData<u8>, refs), 0);
+        assert_eq!(offset_of!(arcdata::ArcData<u8>, weak), 64);
+        assert!(offset_of!(arcdata::ArcData<u8>, data) >= 64 + 8);
+
+        // Slices are laid out by hand, which must agree with where the compiler puts the elements.
+        let arc: Arc<[u64]> = Arc::from(vec![4, 2]);
+        let header =
+            Arc::as_ptr(&arc).cast::<u8>() as usize - offset_of!(arcdata::ArcData<[u64; 0]>, data);
+        assert_eq!(header % 64, 0);
+        assert_eq!(*arc, [4, 2]);
+    }
+
+    /// Passes everything on to the system allocator, counting how many allocations each thread makes (and frees),
+    /// so that tests can check something doesn't allocate, or frees exactly once (without the other tests running
+    /// in parallel getting in the way).
+    struct CountingAllocator;
+
+    std::thread_local! {
+        static ALLOCATIONS: core::cell::Cell<usize> = const { core::cell::Cell::new(0) };
+        static DEALLOCATIONS: core::cell::Cell<usize> = const { core::cell::Cell::new(0) };
+    }
+
+    unsafe impl core::alloc::GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: core::alloc::Layout) -> *mut u8 {
+            // The thread local may already be gone, while a thread is exiting.
+            let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+            unsafe { std::alloc::System.alloc(layout) }
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: core::alloc::Layout) {
+            let _ = DEALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+            unsafe { std::alloc::System.dealloc(ptr, layout) }
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    /// The number of allocations the current thread has made so far.
+    fn allocations() -> usize {
+        ALLOCATIONS.with(|n| n.get())
+    }
+
+    /// The number of allocations the current thread has freed so far.
+    fn deallocations() -> usize {
+        DEALLOCATIONS.with(|n| n.get())
+    }
+
+    #[test]
+    fn empty_default_test() {
+        use core::ffi::CStr;
+
+        let before = allocations();
+        for _ in 0..1000 {
+            let s: Arc<str> = Arc::default();
+            let n: Arc<[u64]> = Arc::default();
+            let strings: Arc<[String]> = Arc::default();
+            let c: Arc<CStr> = Arc::default();
+            let clones = (s.clone(), n.clone(), strings.clone(), c.clone());
+            let weak = Arc::downgrade(&strings);
+            assert!(weak.upgrade().is_some());
+            drop((s, n, strings, c, clones, weak));
+        }
+        assert_eq!(allocations(), before);
+
+        let mut bytes: Arc<[u8]> = Arc::default();
+        let s: Arc<str> = Arc::default();
+        let c: Arc<CStr> = Arc::default();
+        assert!(bytes.is_empty());
+        assert_eq!(&*s, "");
+        assert_eq!(&*c, c"");
+        assert_eq!(c.to_bytes_with_nul(), [0]);
+        // The static's own reference is counted too, so there's never just the one.
+        assert!(Arc::strong_count(&bytes) >= 2);
+        assert!(Arc::get_mut(&mut bytes).is_none());
+
+        // They all share one allocation, whatever the element type.
+        let units: Arc<[()]> = Arc::default();
+        let words: Arc<[u64]> = Arc::default();
+        assert!(Arc::ptr_eq(&bytes, &Arc::default()));
+        assert_eq!(
+            Arc::as_ptr(&s).cast::<u8>(),
+            Arc::as_ptr(&bytes).cast::<u8>()
+        );
+        assert_eq!(c.as_ptr().cast::<u8>(), Arc::as_ptr(&bytes).cast::<u8>());
+        assert_eq!(
+            Arc::as_ptr(&units).cast::<u8>(),
+            Arc::as_ptr(&bytes).cast::<u8>()
+        );
+        assert_eq!(Arc::as_ptr(&words).cast::<u8>().align_offset(8), 0);
+
+        // Types needing more alignment than the static has get an allocation of their own.
+        #[repr(align(128))]
+        struct Aligned;
+        let before = allocations();
+        let a: Arc<[Aligned]> = Arc::default();
+        let b: Arc<[Aligned]> = Arc::default();
+        assert_eq!(allocations(), before + 2);
+        assert!(!Arc::ptr_eq(&a, &b));
+        assert_eq!(Arc::strong_count(&a), 1);
+        assert_eq!(Arc::as_ptr(&a).cast::<u8>().align_offset(128), 0);
+    }
+
+    #[test]
+    fn str_into_bytes_test() {
+        for str_last in [false, true] {
+            let s: Arc<str> = Arc::from("héllo");
+            let before = (allocations(), deallocations());
+            let bytes: Arc<[u8]> = Arc::from(s.clone());
+            assert_eq!(&*bytes, "héllo".as_bytes());
+            assert_eq!(bytes.as_ptr(), s.as_ptr());
+            assert_eq!(Arc::strong_count(&s), 2);
+
+            let weak = Arc::downgrade(&bytes);
+            if str_last {
+                drop(bytes);
+                assert_eq!(deallocations(), before.1);
+                assert_eq!(&*s, "héllo");
+                drop(s);
+            } else {
+                drop(s);
+                assert_eq!(deallocations(), before.1);
+                assert_eq!(&*bytes, "héllo".as_bytes());
+                drop(bytes);
+            }
+            assert!(weak.upgrade().is_none());
+            drop(weak);
+            // Nothing was copied, and the one allocation was freed once.
+            assert_eq!(allocations(), before.0);
+            assert_eq!(deallocations(), before.1 + 1);
+        }
+
+        let empty: Arc<[u8]> = Arc::from(Arc::<str>::default());
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn drop_iterative_test() {
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        struct Node {
+            next: Option<Arc<Node>>,
+            _detect: DetectDrop<'static>,
+        }
+
+        let node = |next| {
+            Arc::new(Node {
+                next,
+                _detect: DetectDrop::new(&NUM_DROPS),
+            })
+        };
+
+        // Far more than the stack could take recursively.
+        let len = if cfg!(miri) { 1000 } else { 1_000_000 };
+        let mut list = node(None);
+        for _ in 1..len {
+            list = node(Some(list));
+        }
+        Arc::drop_iterative(list, |node| node.next.take());
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), len);
+
+        // A node that's still shared stops the unlinking, and keeps the rest of the list alive. Weak references
+        // don't.
+        let tail = node(None);
+        let weak_tail = Arc::downgrade(&tail);
+        let shared = node(Some(tail));
+        let head = node(Some(shared.clone()));
+        let weak_head = Arc::downgrade(&head);
+        Arc::drop_iterative(head, |node| node.next.take());
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), len + 1);
+        assert!(weak_head.upgrade().is_none());
+        assert!(weak_tail.upgrade().is_some());
+        Arc::drop_iterative(shared, |node| node.next.take());
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), len + 3);
+        assert!(weak_tail.upgrade().is_none());
+
+        // Any number of children, with a finalizer running first, while they're still there.
+        struct Tree(Vec<Arc<Tree>>);
+        let leaves = || (0..3).map(|_| Arc::new(Tree(vec![]))).collect();
+        let finalized = Arc::new(AtomicUsize::new(0));
+        let root = Arc::new_with_finalizer(
+            Tree(vec![Arc::new(Tree(leaves())), Arc::new(Tree(leaves()))]),
+            {
+                let finalized = finalized.clone();
+                move |tree: &mut Tree| {
+                    finalized.store(tree.0.len(), Ordering::Relaxed);
+                }
+            },
+        );
+        let mut unlinked = 0;
+        Arc::drop_iterative(root, |tree| {
+            unlinked += 1;
+            core::mem::take(&mut tree.0)
+        });
+        assert_eq!(unlinked, 9);
+        assert_eq!(finalized.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn project_test() {
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        struct Big {
+            name: String,
+            samples: Vec<u32>,
+            _detect: DetectDrop<'static>,
+        }
+
+        let arc = Arc::new(Big {
+            name: String::from("sensor"),
+            samples: (0..10).collect(),
+            _detect: DetectDrop::new(&NUM_DROPS),
+        });
+        let weak = Arc::downgrade(&arc);
+
+        // A field.
+        let name = Arc::project(arc.clone(), |big| &big.name);
+        assert_eq!(*name, "sensor");
+        assert_eq!(format!("{name}, {name:?}"), "sensor, \"sensor\"");
+        assert_eq!(Arc::strong_count(&arc), 2);
+
+        // A subslice, narrowed down further.
+        let samples = Arc::project(arc, |big| &big.samples[..]);
+        let middle = ArcProjection::project(samples.clone(), |samples| &samples[3..6]);
+        assert_eq!(*middle, [3, 4, 5]);
+        assert!(core::ptr::eq(&middle[0], &samples[3]));
+        drop(samples);
+
+        // Whichever projection goes last drops the data (once), and frees the allocation.
+        let clone = middle.clone();
+        assert_eq!(*clone, [3, 4, 5]);
+        drop(name);
+        drop(middle);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 0);
+        assert!(weak.upgrade().is_some());
+        let arc = ArcProjection::into_arc(clone);
+        assert_eq!(Arc::strong_count(&arc), 1);
+        drop(arc);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 1);
+        assert!(weak.upgrade().is_none());
+
+        // Projections can be shared between threads like the `Arc`s they hold.
+        let word = Arc::project(Arc::<str>::from("hello world"), |s| &s[6..]);
+        std::thread::scope(|s| {
+            s.spawn(|| assert_eq!(&*word, "world"));
+            let word = word.clone();
+            s.spawn(move || assert_eq!(&*word, "world"));
+        });
+    }
+
+    #[test]
+    fn from_elem_test() {
+        static NUM_CLONES: AtomicUsize = AtomicUsize::new(0);
+        static NUM_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        struct Detect(usize, DetectDrop<'static>);
+
+        impl Clone for Detect {
+            fn clone(&self) -> Self {
+                let n = NUM_CLONES.fetch_add(1, Ordering::Relaxed);
+                if n == 10 {
+                    panic!("clone failed");
+                }
+                Self(n, self.1.clone())
+            }
+        }
+
+        let original = || Detect(usize::MAX, DetectDrop::new(&NUM_DROPS));
+
+        // One clone short of `n`: the original goes last.
+        let arc = Arc::from_elem(original(), 5);
+        assert_eq!(NUM_CLONES.load(Ordering::Relaxed), 4);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 0);
+        let values: Vec<usize> = arc.iter().map(|x| x.0).collect();
+        assert_eq!(values, [0, 1, 2, 3, usize::MAX]);
+        drop(arc);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 5);
+
+        // The clones made before the panic are dropped, and so is the original.
+        let result = std::panic::catch_unwind(|| Arc::from_elem(original(), 10));
+        assert!(result.is_err());
+        assert_eq!(NUM_CLONES.load(Ordering::Relaxed), 11);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 5 + 6 + 1);
+
+        // No clone needed for a single element, and none kept for zero.
+        let arc = Arc::from_elem(original(), 1);
+        assert_eq!(arc.len(), 1);
+        let before = allocations();
+        let empty = Arc::from_elem(original(), 0);
+        assert!(empty.is_empty());
+        assert_eq!(allocations(), before);
+        assert!(Arc::ptr_eq(&empty, &Arc::default()));
+        assert_eq!(NUM_CLONES.load(Ordering::Relaxed), 11);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 13);
+        drop(arc);
+        assert_eq!(NUM_DROPS.load(Ordering::Relaxed), 14);
+
+        let squares = Arc::from_elem(vec![1, 4, 9], 3);
+        assert!(squares.iter().all(|v| *v == [1, 4, 9]));
+    }
+
+    #[test]
+    fn try_clone_test() {
+        let arc = Arc::new(String::from("hello"));
+        let clone = Arc::try_clone(&arc).unwrap();
+        assert!(Arc::ptr_eq(&arc, &clone));
+        assert_eq!(Arc::strong_count(&arc), 2);
+
+        // Pretend there are almost as many `Arc`s as there may be.
+        let data = unsafe { arcdata::ArcData::from_data_ptr(Arc::as_ptr(&arc)).as_ref() };
+        let extra = arcdata::MAX_REFS - 3;
+        data.refs.fetch_add(extra, Ordering::Relaxed);
+
+        // One more still fits, after that it's an error, and the count is left alone.
+        let last = Arc::try_clone(&arc).unwrap();
+        assert_eq!(Arc::strong_count(&arc), arcdata::MAX_REFS);
+        assert_eq!(Arc::try_clone(&arc).err(), Some(CloneError));
+        assert_eq!(Arc::try_clone(&clone).err(), Some(CloneError));
+        assert_eq!(Arc::strong_count(&arc), arcdata::MAX_REFS);
+        assert_eq!(CloneError.to_string(), "too many references");
+
+        // Making room again.
+        drop(last);
+        assert!(Arc::try_clone(&arc).is_ok());
+        data.refs.fetch_sub(extra, Ordering::Relaxed);
+        assert_eq!(Arc::strong_count(&arc), 2);
+    }
+
+    #[test]
+    fn raw_parts_test() {
+        let bytes: Arc<[u8]> = Arc::from(vec![1, 2, 3]);
+        let weak = Arc::downgrade(&bytes);
+        let (data, len) = Arc::into_raw_parts(bytes);
+        assert_eq!(len, 3);
+        // Nothing changes while the `Arc` is just its parts.
+        assert_eq!(weak.strong_count(), 1);
+        let bytes = unsafe { Arc::<[u8]>::from_raw_parts(data, len) };
+        assert_eq!(*bytes, [1, 2, 3]);
+        assert!(Arc::ptr_eq(&bytes, &weak.upgrade().unwrap()));
+        drop(bytes);
+        assert!(weak.upgrade().is_none());
+
+        let (data, len) = Arc::into_raw_parts(Arc::<str>::from("héllo"));
+        assert_eq!(len, "héllo".len());
+        let s = unsafe { Arc::<str>::from_raw_parts(data, len) };
+        assert_eq!(&*s, "héllo");
+
+        let (data, ()) = Arc::into_raw_parts(Arc::new(String::from("sized")));
+        let sized = unsafe { Arc::<String>::from_raw_parts(data, ()) };
+        assert_eq!(*sized, "sized");
+
+        // The header is stepped over however far the elements' alignment puts them from it (and for the shared
+        // empty default, too).
+        #[derive(Clone, Copy, PartialEq, Debug)]
+        #[repr(align(128))]
+        struct Aligned(u8);
+        let aligned: Arc<[Aligned]> = Arc::from([Aligned(1), Aligned(2)]);
+        let (data, len) = Arc::into_raw_parts(aligned);
+        let aligned = unsafe { Arc::<[Aligned]>::from_raw_parts(data, len) };
+        assert_eq!(*aligned, [Aligned(1), Aligned(2)]);
+        let (data, len) = Arc::into_raw_parts(Arc::<[u64]>::default());
+        let empty = unsafe { Arc::<[u64]>::from_raw_parts(data, len) };
+        assert!(Arc::ptr_eq(&empty, &Arc::default()));
+    }
+
+    #[test]
+    #[cfg(feature = "nightly")]
+    fn raw_parts_dyn_test() {
+        use std::fmt::{self, Debug};
+
+        #[repr(align(64))]
+        struct Aligned(u8);
+
+        impl Debug for Aligned {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "Aligned({})", self.0)
+            }
+        }
+
+        for arc in [
+            Arc::new(vec![4, 2]) as Arc<dyn Debug>,
+            Arc::new(Aligned(7)) as Arc<dyn Debug>,
+            Arc::new(()) as Arc<dyn Debug>,
+        ] {
+            let expected = format!("{:?}", &*arc);
+            let clone = arc.clone();
+            let (data, vtable) = Arc::into_raw_parts(arc);
+            let arc = unsafe { Arc::<dyn Debug>::from_raw_parts(data, vtable) };
+            assert_eq!(format!("{:?}", &*arc), expected);
+            assert!(Arc::ptr_eq(&arc, &clone));
+            assert_eq!(Arc::strong_count(&clone), 2);
+        }
+    }
 }
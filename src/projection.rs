@@ -0,0 +1,137 @@
+use crate::arc::Arc;
+
+use core::borrow::Borrow;
+use core::fmt;
+use core::ops::Deref;
+use core::ptr::NonNull;
+
+/// A reference to part of the data of an [`Arc`] (like one of its fields, or a subslice), created by
+/// [`Arc::project`].
+///
+/// It holds on to the whole `Arc<T>`, so the allocation (and everything else in it) stays alive until the last
+/// projection and `Arc<T>` are gone, but it dereferences to `U`: it can be handed out where only that part
+/// should be visible.
+///
+/// # Examples
+/// ```
+/// use arc::{Arc, ArcProjection};
+/// struct Config {
+///     name: String,
+///     ports: Vec<u16>,
+/// }
+///
+/// let config = Arc::new(Config { name: String::from("server"), ports: vec![80, 443, 8080] });
+/// let name: ArcProjection<Config, String> = Arc::project(config.clone(), |config| &config.name);
+/// let secure = Arc::project(config, |config| &config.ports[1..]);
+/// assert_eq!(*name, "server");
+/// assert_eq!(*secure, [443, 8080]);
+/// ```
+pub struct ArcProjection<T: ?Sized, U: ?Sized> {
+    // Keeps the data `ptr` points into alive. It's only ever handed out by shared reference, so nothing can get at
+    // the data mutably while `ptr` is around.
+    arc: Arc<T>,
+    // Into `arc`'s data (or to something `'static`), as borrowed from it by the function passed to `Arc::project`.
+    ptr: NonNull<U>,
+}
+
+// An `ArcProjection<T, U>` is an `Arc<T>` and a `&U` that borrows from it, so it's `Send` or `Sync` when they
+// both are.
+unsafe impl<T: ?Sized + Send + Sync, U: ?Sized + Sync> Send for ArcProjection<T, U> {}
+unsafe impl<T: ?Sized + Send + Sync, U: ?Sized + Sync> Sync for ArcProjection<T, U> {}
+
+impl<T: ?Sized, U: ?Sized> ArcProjection<T, U> {
+    pub(crate) fn new(arc: Arc<T>, f: impl FnOnce(&T) -> &U) -> Self {
+        // The data lives on the heap, so it stays where it is when `arc` is moved into the projection.
+        let ptr = NonNull::from(f(&arc));
+        Self { arc, ptr }
+    }
+
+    /// Narrows this projection down further, to part of `U`.
+    ///
+    /// # Arguments
+    /// * `this` - The projection to consume.
+    /// * `f` - Returns the part of `U` the new projection dereferences to.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let arc = Arc::new((String::from("hello world"), 42));
+    /// let hello = Arc::project(arc, |pair| &pair.0);
+    /// let world = arc::ArcProjection::project(hello, |s| &s[6..]);
+    /// assert_eq!(&*world, "world");
+    /// ```
+    pub fn project<V: ?Sized>(this: Self, f: impl FnOnce(&U) -> &V) -> ArcProjection<T, V> {
+        let ptr = NonNull::from(f(&this));
+        ArcProjection { arc: this.arc, ptr }
+    }
+
+    /// Returns the `Arc<T>` whose data this projection points into.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::{Arc, ArcProjection};
+    /// let arc = Arc::new((1, 2));
+    /// let second = Arc::project(arc.clone(), |pair| &pair.1);
+    /// assert!(Arc::ptr_eq(ArcProjection::arc(&second), &arc));
+    /// ```
+    pub fn arc(this: &Self) -> &Arc<T> {
+        &this.arc
+    }
+
+    /// Consumes this projection and returns the `Arc<T>` whose data it pointed into.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::{Arc, ArcProjection};
+    /// let second = Arc::project(Arc::new((1, 2)), |pair| &pair.1);
+    /// assert_eq!(*ArcProjection::into_arc(second), (1, 2));
+    /// ```
+    pub fn into_arc(this: Self) -> Arc<T> {
+        this.arc
+    }
+}
+
+impl<T: ?Sized, U: ?Sized> Deref for ArcProjection<T, U> {
+    type Target = U;
+
+    fn deref(&self) -> &U {
+        // `ptr` came from a reference that could live as long as `self.arc`'s data, which `self` keeps alive.
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+/// Clones the `Arc<T>` the projection holds, pointing to the same part of the data.
+impl<T: ?Sized, U: ?Sized> Clone for ArcProjection<T, U> {
+    fn clone(&self) -> Self {
+        Self {
+            arc: self.arc.clone(),
+            ptr: self.ptr,
+        }
+    }
+}
+
+impl<T: ?Sized, U: ?Sized> AsRef<U> for ArcProjection<T, U> {
+    fn as_ref(&self) -> &U {
+        self
+    }
+}
+
+impl<T: ?Sized, U: ?Sized> Borrow<U> for ArcProjection<T, U> {
+    fn borrow(&self) -> &U {
+        self
+    }
+}
+
+/// Formats the part of the data the projection points to, exactly like `U` would.
+impl<T: ?Sized, U: ?Sized + fmt::Debug> fmt::Debug for ArcProjection<T, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// Formats the part of the data the projection points to, exactly like `U` would.
+impl<T: ?Sized, U: ?Sized + fmt::Display> fmt::Display for ArcProjection<T, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
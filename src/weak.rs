@@ -0,0 +1,291 @@
+use crate::allocator::{Allocator, Global};
+use crate::arcdata::*;
+use crate::Arc;
+
+use core::fmt;
+#[cfg(feature = "nightly")]
+use core::marker::Unsize;
+#[cfg(feature = "nightly")]
+use core::ops::{CoerceUnsized, DispatchFromDyn};
+use core::panic::{RefUnwindSafe, UnwindSafe};
+
+/// A non-owning reference to the data of an [`Arc`](crate::Arc).
+///
+/// A `Weak<T>` keeps the allocation alive, but not the data inside it: dropping the last `Arc<T>` still
+/// runs `T`'s destructor, even while `Weak<T>`s exist.
+pub struct Weak<T: ?Sized, A: Allocator = Global> {
+    pub(crate) data: NonNull<ArcData<T, A>>,
+}
+
+impl<T> Weak<T> {
+    /// Creates a new `Weak<T>` that doesn't point to any allocation, without allocating anything.
+    ///
+    /// Calling [`Weak::upgrade`] on it always returns [`Option::None`].
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Weak;
+    /// let weak: Weak<i32> = Weak::new();
+    /// assert!(weak.upgrade().is_none());
+    /// ```
+    pub const fn new() -> Self {
+        // `usize::MAX` can never be the address of an (aligned) `ArcData<T>`, so it's a safe sentinel. It is
+        // never dereferenced: every function checks for it first (see `data`).
+        Self {
+            data: unsafe { NonNull::new_unchecked(core::ptr::without_provenance_mut(usize::MAX)) },
+        }
+    }
+
+    /// Turns a raw pointer obtained from [`Weak::into_raw`] back into a `Weak<T>`, taking over the weak
+    /// reference it stood for.
+    ///
+    /// # Safety
+    /// `ptr` must have come from [`Weak::into_raw`] on a `Weak<T>` (with the same `T`), and each such pointer
+    /// may only be turned back into a `Weak<T>` once.
+    ///
+    /// # Examples
+    /// A `Weak<T>` created by [`Weak::new`] comes back dangling:
+    /// ```
+    /// use arc::Weak;
+    /// let ptr = Weak::<i32>::new().into_raw();
+    /// let weak = unsafe { Weak::from_raw(ptr) };
+    /// assert!(weak.upgrade().is_none());
+    /// assert!(weak.ptr_eq(&Weak::new()));
+    /// ```
+    pub unsafe fn from_raw(ptr: *const T) -> Self {
+        if ptr.addr() == usize::MAX {
+            // The sentinel returned for a `Weak::new`.
+            return Self::new();
+        }
+        Self {
+            data: unsafe { ArcData::from_data_ptr(ptr) },
+        }
+    }
+}
+
+impl<T: ?Sized> Weak<T> {
+    /// Consumes this `Weak<T>` and returns a raw pointer to its data (see [`Weak::as_ptr`]), without changing the
+    /// weak count. Use [`Weak::from_raw`] to turn it back into a `Weak<T>`, or the weak count will never reach
+    /// zero (and the allocation will be leaked).
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::{Arc, Weak};
+    /// let arc = Arc::new(42);
+    /// let ptr = Arc::downgrade(&arc).into_raw();
+    /// let weak = unsafe { Weak::from_raw(ptr) };
+    /// assert_eq!(*weak.upgrade().unwrap(), 42);
+    /// ```
+    pub fn into_raw(self) -> *const T {
+        let ptr = self.as_ptr();
+        core::mem::forget(self);
+        ptr
+    }
+}
+
+impl<T: ?Sized, A: Allocator> Weak<T, A> {
+    /// Attempts to upgrade this `Weak<T>` into an [`Arc`].
+    ///
+    /// Returns [`Option::None`] if the data has already been dropped (i.e. there are no `Arc<T>`s left).
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let arc = Arc::new(42);
+    /// let weak = Arc::downgrade(&arc);
+    /// assert_eq!(*weak.upgrade().unwrap(), 42);
+    /// drop(arc);
+    /// assert!(weak.upgrade().is_none());
+    /// ```
+    pub fn upgrade(&self) -> Option<Arc<T, A>> {
+        // A plain `fetch_add` would be wrong here: if the count was 0, the data has already been dropped (or is
+        // being dropped right now), and incrementing it would "resurrect" it. Instead, we only ever increment a
+        // non-zero count, which a compare-exchange loop lets us do atomically.
+        let data = self.data()?;
+        let mut n = data.refs.load(Ordering::Relaxed);
+        loop {
+            if n == 0 {
+                return None;
+            }
+            if n == WAITING | 1 {
+                // The last `Arc<T>` besides one waiting in `Arc::into_inner_blocking` is being dropped. Wait until
+                // it's done waking that one up (which doesn't take long), or a second `Arc<T>` might try to as well.
+                core::hint::spin_loop();
+                n = data.refs.load(Ordering::Relaxed);
+                continue;
+            }
+            // `WAITING` doesn't count (see `Arc::into_inner_blocking`).
+            if n & !WAITING > MAX_REFS {
+                abort();
+            }
+            // If we succeed, the count was non-zero at the instant of the exchange (it's part of the
+            // all-thread-spanning total-modification-order), so the `Arc<T>` that kept it non-zero can't have
+            // dropped the data yet.
+            // Acquire on success makes sure the returned `Arc<T>` sees fully-initialized data, even if it was
+            // written after this `Weak<T>` was created (as long as the strong count was then released).
+            // Relaxed on failure is enough since we don't touch the data in that case, we just try again.
+            if let Err(e) =
+                data.refs
+                    .compare_exchange_weak(n, n + 1, Ordering::Acquire, Ordering::Relaxed)
+            {
+                // Either someone else changed the count, or `compare_exchange_weak` spuriously failed.
+                n = e;
+                continue;
+            }
+            return Some(Arc::from_inner(self.data));
+        }
+    }
+
+    /// Returns the number of [`Arc`]s pointing to this allocation, or 0 if this `Weak<T>` was created by
+    /// [`Weak::new`].
+    ///
+    /// Like [`Arc::strong_count`], this is only a snapshot: other threads may clone or drop their `Arc<T>`s
+    /// at any time.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let arc = Arc::new(42);
+    /// let weak = Arc::downgrade(&arc);
+    /// assert_eq!(weak.strong_count(), 1);
+    /// drop(arc);
+    /// assert_eq!(weak.strong_count(), 0);
+    /// ```
+    pub fn strong_count(&self) -> usize {
+        self.data()
+            .map_or(0, |data| data.refs.load(Ordering::Acquire) & !WAITING)
+    }
+
+    /// Returns the number of `Weak<T>`s pointing to this allocation (including this one), or 0 if there are no
+    /// [`Arc`]s left or this `Weak<T>` was created by [`Weak::new`].
+    ///
+    /// Like [`Arc::weak_count`], this is only a snapshot: other threads may create or drop `Weak<T>`s at any time.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let arc = Arc::new(42);
+    /// let weak = Arc::downgrade(&arc);
+    /// assert_eq!(weak.weak_count(), 1);
+    /// drop(arc);
+    /// assert_eq!(weak.weak_count(), 0);
+    /// ```
+    pub fn weak_count(&self) -> usize {
+        let Some(data) = self.data() else {
+            return 0;
+        };
+        let weak = data.weak.load(Ordering::Acquire);
+        if data.refs.load(Ordering::Acquire) == 0 {
+            // Without any `Arc<T>`s, the data is gone, so report 0 the same way a dangling `Weak<T>` would.
+            0
+        } else {
+            // While there are `Arc<T>`s, the weak count includes the implicit weak reference they collectively hold.
+            // Since the two loads above aren't a single atomic step, the last `Arc<T>` may have given it back in
+            // between, so don't underflow.
+            weak.saturating_sub(1)
+        }
+    }
+
+    /// Returns a raw pointer to the data this `Weak<T>` points to, without changing any reference counts.
+    ///
+    /// The pointer is only valid for reads while there is an [`Arc`] keeping the data alive. If this `Weak<T>`
+    /// was created by [`Weak::new`], the returned pointer is a sentinel that must never be dereferenced.
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::Arc;
+    /// let arc = Arc::new(42);
+    /// let weak = Arc::downgrade(&arc);
+    /// assert_eq!(unsafe { *weak.as_ptr() }, 42);
+    /// ```
+    pub fn as_ptr(&self) -> *const T {
+        if self.data().is_none() {
+            return self.data.as_ptr() as *const T;
+        }
+        // This never creates a reference, so it's fine even if the data has already been dropped.
+        ArcData::data_ptr(self.data)
+    }
+
+    /// Returns `true` if the two `Weak<T>`s point to the same allocation, or if neither points to any allocation
+    /// (both were created by [`Weak::new`]).
+    ///
+    /// # Examples
+    /// ```
+    /// use arc::{Arc, Weak};
+    /// let arc = Arc::new(42);
+    /// let weak = Arc::downgrade(&arc);
+    /// assert!(weak.ptr_eq(&weak.clone()));
+    /// assert!(!weak.ptr_eq(&Arc::downgrade(&Arc::new(42))));
+    /// assert!(Weak::<i32>::new().ptr_eq(&Weak::new()));
+    /// ```
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        // Comparing the `ArcData<T>` pointers works for dangling `Weak<T>`s too, since they all use the same sentinel.
+        core::ptr::addr_eq(self.data.as_ptr(), other.data.as_ptr())
+    }
+
+    // Private functions
+    /// Returns [`Option::None`] if this `Weak<T>` was created by [`Weak::new`].
+    fn data(&self) -> Option<&ArcData<T, A>> {
+        if self.data.as_ptr().addr() == usize::MAX {
+            None
+        } else {
+            Some(unsafe { self.data.as_ref() })
+        }
+    }
+}
+
+impl<T: ?Sized, A: Allocator> Clone for Weak<T, A> {
+    fn clone(&self) -> Self {
+        // Relaxed is enough: this `Weak<T>` keeps the allocation alive for the duration of the increment,
+        // and the new `Weak<T>` doesn't need to observe anything done by other threads through the counters.
+        if let Some(data) = self.data() {
+            if data.weak.fetch_add(1, Ordering::Relaxed) > MAX_REFS {
+                abort();
+            }
+        }
+        Self { data: self.data }
+    }
+}
+
+impl<T: ?Sized, A: Allocator> fmt::Debug for Weak<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The data may have been dropped already, so don't try to print it.
+        write!(f, "(Weak)")
+    }
+}
+
+impl<T> Default for Weak<T> {
+    /// Same as [`Weak::new`].
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: ?Sized, A: Allocator> Drop for Weak<T, A> {
+    fn drop(&mut self) {
+        // Same reasoning as in `Arc<T>`'s `Drop`: the release decrement "happens before" the acquire fence of
+        // whoever drops the last weak reference, so nothing can still be using the allocation when it's freed.
+        let Some(data) = self.data() else {
+            // Created by `Weak::new`, so there's no allocation to free.
+            return;
+        };
+        if data.weak.fetch_sub(1, Ordering::Release) == 1 {
+            fence(Ordering::Acquire);
+            unsafe { ArcData::deallocate(self.data) };
+        }
+    }
+}
+
+// The allocator is shared just like the data (and used by whichever thread frees the allocation).
+unsafe impl<T: ?Sized + Send + Sync, A: Allocator + Send + Sync> Send for Weak<T, A> {}
+unsafe impl<T: ?Sized + Send + Sync, A: Allocator + Send + Sync> Sync for Weak<T, A> {}
+
+// See the matching impls for `Arc<T>`.
+impl<T: ?Sized + RefUnwindSafe, A: Allocator + RefUnwindSafe> UnwindSafe for Weak<T, A> {}
+impl<T: ?Sized + RefUnwindSafe, A: Allocator + RefUnwindSafe> RefUnwindSafe for Weak<T, A> {}
+
+// See the matching impls for `Arc<T>`.
+#[cfg(feature = "nightly")]
+impl<T: ?Sized + Unsize<U>, U: ?Sized, A: Allocator> CoerceUnsized<Weak<U, A>> for Weak<T, A> {}
+#[cfg(feature = "nightly")]
+impl<T: ?Sized + Unsize<U>, U: ?Sized> DispatchFromDyn<Weak<U>> for Weak<T> {}